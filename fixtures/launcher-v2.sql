@@ -0,0 +1,49 @@
+-- Tables of launcher-v2.sqlite as the paradox launcher creates them, the columns this program doesn't use included.
+CREATE TABLE mods (
+    id char(36) NOT NULL PRIMARY KEY,
+    pdxId VARCHAR(255) UNIQUE,
+    steamId VARCHAR(255) UNIQUE,
+    gameRegistryId TEXT UNIQUE,
+    name VARCHAR(255),
+    displayName VARCHAR(255),
+    thumbnailUrl VARCHAR(255),
+    thumbnailPath VARCHAR(255),
+    version VARCHAR(255),
+    tags JSON,
+    requiredVersion VARCHAR(255),
+    arch VARCHAR(255),
+    os VARCHAR(255),
+    repositoryPath TEXT,
+    dirPath TEXT,
+    archivePath TEXT,
+    status VARCHAR(255) NOT NULL,
+    source VARCHAR(255) NOT NULL,
+    timeUpdated BIGINT,
+    timeInstalled BIGINT,
+    timeLastPlayed BIGINT,
+    isNew BOOLEAN DEFAULT 0
+);
+
+CREATE TABLE playsets (
+    id char(36) NOT NULL PRIMARY KEY,
+    name VARCHAR(255) NOT NULL,
+    isActive BOOLEAN,
+    loadOrder VARCHAR(255),
+    pdxId VARCHAR(255),
+    pdxUserId VARCHAR(255),
+    createdOn DATETIME NOT NULL,
+    updatedOn DATETIME,
+    syncedOn DATETIME,
+    lastServerChecksum TEXT,
+    isRemoved BOOLEAN NOT NULL DEFAULT 0,
+    hasNotApprovedChanges BOOLEAN NOT NULL DEFAULT 0,
+    syncState TEXT
+);
+
+CREATE TABLE playsets_mods (
+    playsetId char(36) NOT NULL REFERENCES playsets (id) ON DELETE CASCADE,
+    modId char(36) NOT NULL REFERENCES mods (id) ON DELETE CASCADE,
+    enabled BOOLEAN DEFAULT 1,
+    position INTEGER,
+    PRIMARY KEY (playsetId, modId)
+);
@@ -0,0 +1,122 @@
+//! Services mods are downloaded from.
+//!
+//! Every service implements [`DownloadBackend`], the backend in use is chosen by [`BackendConfig`] in the configuration.
+
+mod steamcmd;
+mod steamworkshopdownloader;
+
+use std::{path::PathBuf, sync::Arc};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use reqwest::{Client, Request};
+use serde::{Deserialize, Serialize};
+use tempfile::NamedTempFile;
+
+use crate::progress::DownloadProgress;
+
+pub use steamcmd::SteamCmd;
+pub use steamworkshopdownloader::SteamWorkshopDownloader;
+
+/// Identifies a download requested from a backend.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ticket(pub String);
+
+/// State of a requested download.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Status {
+    /// The backend is still preparing the file, with the state it reported.
+    Pending(String),
+    /// The file can be fetched.
+    Ready,
+    /// The backend gave up, with the reason it reported.
+    Failed(String),
+}
+
+/// A downloaded workshop item.
+#[derive(Debug)]
+pub enum Download {
+    /// A zip archive of the item in a temporary file, which is deleted when dropped.
+    Archive(NamedTempFile),
+    /// A zip archive on this computer, which is left untouched by the installation.
+    LocalArchive(PathBuf),
+    /// A directory with the item's files, which is left untouched by the installation.
+    Directory(PathBuf),
+}
+
+/// How a download which is ready is fetched.
+#[derive(Debug)]
+pub enum Fetch {
+    /// The zip archive of the item is the body of this request, which is retried and resumed by the caller.
+    Request(Box<Request>),
+    /// The backend already downloaded the item.
+    Done(Download),
+}
+
+/// A service which downloads workshop items.
+///
+/// A download is requested once, polled with [`status`](Self::status) until it is ready and then fetched.
+#[async_trait]
+pub trait DownloadBackend: Send + Sync {
+    /// Name of the backend shown to the user.
+    fn name(&self) -> &str;
+
+    /// Requests the download of a workshop item of the game with the steam app id `app_id`.
+    ///
+    /// Backends which download the item right away show their state on `progress`.
+    async fn request(
+        &self,
+        item_id: u64,
+        app_id: u64,
+        progress: &DownloadProgress,
+    ) -> Result<Ticket>;
+
+    /// Checks whether a requested download is ready.
+    async fn status(&self, ticket: &Ticket) -> Result<Status>;
+
+    /// Fetches a download which is ready.
+    async fn fetch(&self, ticket: &Ticket) -> Result<Fetch>;
+}
+
+/// Configuration of the backend mods are downloaded from.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum BackendConfig {
+    /// steamworkshopdownloader.io or a mirror with the same API.
+    SteamWorkshopDownloader {
+        /// Address of the service, without a trailing slash.
+        #[serde(default = "SteamWorkshopDownloader::default_base_url")]
+        base_url: String,
+    },
+    /// A local steamcmd installation.
+    SteamCmd {
+        /// Path of the steamcmd executable.
+        #[serde(default = "SteamCmd::default_path")]
+        path: String,
+        /// Steam account to log in with, items of free games can be downloaded anonymously.
+        #[serde(default)]
+        username: Option<String>,
+    },
+}
+
+impl Default for BackendConfig {
+    fn default() -> Self {
+        Self::SteamWorkshopDownloader {
+            base_url: SteamWorkshopDownloader::default_base_url(),
+        }
+    }
+}
+
+impl BackendConfig {
+    /// Creates the configured backend, sharing `client` for its requests.
+    pub fn build(&self, client: Client) -> Arc<dyn DownloadBackend> {
+        match self {
+            Self::SteamWorkshopDownloader { base_url } => {
+                Arc::new(SteamWorkshopDownloader::new(client, base_url.clone()))
+            }
+            Self::SteamCmd { path, username } => {
+                Arc::new(SteamCmd::new(path.clone(), username.clone()))
+            }
+        }
+    }
+}
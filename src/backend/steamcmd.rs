@@ -0,0 +1,292 @@
+//! Downloads through a local steamcmd installation.
+
+use std::{collections::HashMap, path::PathBuf, process::Stdio, sync::Mutex};
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, BufReader},
+    process::Command,
+};
+
+use super::{Download, DownloadBackend, Fetch, Status, Ticket};
+use crate::progress::DownloadProgress;
+
+/// Backend running `steamcmd +workshop_download_item`.
+///
+/// The item is downloaded completely while it is requested, the installation copies it from steamcmd's workshop folder.
+/// Accounts other than `anonymous` need cached credentials, otherwise steamcmd asks for them on the terminal.
+pub struct SteamCmd {
+    /// Path of the steamcmd executable.
+    path: String,
+    /// Steam account to log in with.
+    username: Option<String>,
+    /// Outcome of each finished download, either the item's directory or the error steamcmd reported.
+    results: Mutex<HashMap<String, Result<PathBuf, String>>>,
+    /// Held while steamcmd runs, as several instances can't share one installation.
+    running: tokio::sync::Mutex<()>,
+}
+
+/// Meaning of a line steamcmd printed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Line {
+    /// Progress worth showing to the user.
+    Progress,
+    /// The item was downloaded into the directory.
+    Success(PathBuf),
+    /// The download or the login failed.
+    Failure(String),
+    /// Anything else.
+    Other,
+}
+
+/// Interprets a line of steamcmd output while downloading `item_id`.
+fn parse_line(line: &str, item_id: u64) -> Line {
+    let line = line.trim();
+    let item = item_id.to_string();
+
+    if line.starts_with("Success. Downloaded item")
+        && line.split_whitespace().nth(3) == Some(item.as_str())
+    {
+        line.split('"')
+            .nth(1)
+            .map_or(Line::Other, |path| Line::Success(PathBuf::from(path)))
+    } else if line.starts_with("ERROR!") || line.contains("FAILED") {
+        Line::Failure(line.to_string())
+    } else if line.starts_with("Downloading item")
+        || line.starts_with("Logging in")
+        || line.contains("progress:")
+    {
+        Line::Progress
+    } else {
+        Line::Other
+    }
+}
+
+impl SteamCmd {
+    /// Instanciates a new backend using the steamcmd executable at `path`.
+    pub fn new(path: String, username: Option<String>) -> Self {
+        Self {
+            path,
+            username,
+            results: Mutex::new(HashMap::new()),
+            running: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    /// Default path of the steamcmd executable, which is looked up in `PATH`.
+    pub fn default_path() -> String {
+        String::from("steamcmd")
+    }
+
+    /// Runs steamcmd and returns the directory of the downloaded item or the error it reported.
+    ///
+    /// The progress steamcmd prints is shown on `progress`, what it printed to stderr is added to its errors.
+    async fn download(
+        &self,
+        item_id: u64,
+        app_id: u64,
+        progress: &DownloadProgress,
+    ) -> Result<Result<PathBuf, String>> {
+        let _running = self.running.lock().await;
+
+        let mut child = Command::new(&self.path)
+            .arg("+login")
+            .arg(self.username.as_deref().unwrap_or("anonymous"))
+            .arg("+workshop_download_item")
+            .arg(app_id.to_string())
+            .arg(item_id.to_string())
+            .arg("+quit")
+            .stdin(Stdio::inherit())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|error| anyhow!("Could not start {}: {}", self.path, error))?;
+
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or_else(|| anyhow!("steamcmd output missing"))?;
+        let mut stderr = child
+            .stderr
+            .take()
+            .ok_or_else(|| anyhow!("steamcmd error output missing"))?;
+        let errors = tokio::spawn(async move {
+            let mut errors = String::new();
+            let _read = stderr.read_to_string(&mut errors).await;
+            errors
+        });
+        let mut lines = BufReader::new(stdout).lines();
+        let mut outcome = None;
+
+        while let Some(line) = lines.next_line().await? {
+            match parse_line(&line, item_id) {
+                Line::Progress => progress.set_message(format!("steamcmd: {}", line.trim())),
+                Line::Success(path) => outcome = Some(Ok(path)),
+                Line::Failure(error) => outcome = Some(Err(error)),
+                Line::Other => {}
+            }
+        }
+
+        let status = child.wait().await?;
+        let errors = errors.await.unwrap_or_default();
+        let errors = errors.trim();
+        Ok(match outcome {
+            Some(Ok(path)) => Ok(path),
+            Some(Err(error)) if errors.is_empty() => Err(error),
+            Some(Err(error)) => Err(format!("{}\n{}", error, errors)),
+            None if errors.is_empty() => Err(format!(
+                "steamcmd exited with {} without downloading item {}",
+                status, item_id
+            )),
+            None => Err(format!(
+                "steamcmd exited with {} without downloading item {}:\n{}",
+                status, item_id, errors
+            )),
+        })
+    }
+}
+
+#[async_trait]
+impl DownloadBackend for SteamCmd {
+    fn name(&self) -> &str {
+        "steamcmd"
+    }
+
+    async fn request(
+        &self,
+        item_id: u64,
+        app_id: u64,
+        progress: &DownloadProgress,
+    ) -> Result<Ticket> {
+        let outcome = self.download(item_id, app_id, progress).await?;
+        let ticket = Ticket(item_id.to_string());
+        let _old = self
+            .results
+            .lock()
+            .expect("steamcmd results poisoned")
+            .insert(ticket.0.clone(), outcome);
+        Ok(ticket)
+    }
+
+    /// Failed downloads are forgotten once their status was reported, as they are never fetched.
+    async fn status(&self, ticket: &Ticket) -> Result<Status> {
+        let mut results = self.results.lock().expect("steamcmd results poisoned");
+        Ok(match results.get(&ticket.0) {
+            Some(Ok(_)) => Status::Ready,
+            Some(Err(_)) => Status::Failed(
+                results
+                    .remove(&ticket.0)
+                    .and_then(Result::err)
+                    .unwrap_or_default(),
+            ),
+            None => Status::Failed(String::from("unknown download")),
+        })
+    }
+
+    async fn fetch(&self, ticket: &Ticket) -> Result<Fetch> {
+        match self
+            .results
+            .lock()
+            .expect("steamcmd results poisoned")
+            .remove(&ticket.0)
+        {
+            Some(Ok(path)) => Ok(Fetch::Done(Download::Directory(path))),
+            Some(Err(error)) => Err(anyhow!(error)),
+            None => Err(anyhow!("unknown download")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, os::unix::fs::PermissionsExt};
+
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::progress::Progress;
+
+    #[test]
+    fn steamcmd_output_is_parsed() {
+        let item_id = 1_121_692_237;
+        for (line, expected) in [
+            (
+                "Redirecting stderr to '/home/user/.steam/steamcmd/logs/stderr.txt'",
+                Line::Other,
+            ),
+            ("Loading Steam API...OK", Line::Other),
+            (
+                "Logging in user 'anonymous' to Steam Public...OK",
+                Line::Progress,
+            ),
+            (
+                "Logging in user 'player' to Steam Public...FAILED login with result code Invalid Password",
+                Line::Failure(String::from(
+                    "Logging in user 'player' to Steam Public...FAILED login with result code Invalid Password",
+                )),
+            ),
+            ("Downloading item 1121692237 ...", Line::Progress),
+            (
+                " Update state (0x61) downloading, progress: 45.23 (123456 / 272727)",
+                Line::Progress,
+            ),
+            (
+                "Success. Downloaded item 1121692237 to \"/home/user/.steam/steamcmd/steamapps/workshop/content/281990/1121692237\" (24018842 bytes) ",
+                Line::Success(PathBuf::from(
+                    "/home/user/.steam/steamcmd/steamapps/workshop/content/281990/1121692237",
+                )),
+            ),
+            (
+                "Success. Downloaded item 11216922370 to \"/home/user/.steam/steamcmd/steamapps/workshop/content/281990/11216922370\" (1024 bytes) ",
+                Line::Other,
+            ),
+            (
+                "ERROR! Download item 1121692237 failed (File Not Found).",
+                Line::Failure(String::from(
+                    "ERROR! Download item 1121692237 failed (File Not Found).",
+                )),
+            ),
+            (
+                "ERROR! Timeout downloading item 1121692237",
+                Line::Failure(String::from("ERROR! Timeout downloading item 1121692237")),
+            ),
+        ]
+        .iter()
+        {
+            assert_eq!(parse_line(line, item_id), *expected, "{}", line);
+        }
+    }
+
+    /// Writes a script standing in for steamcmd which runs `script`.
+    fn steamcmd(dir: &TempDir, script: &str) -> String {
+        let path = dir.path().join("steamcmd");
+        fs::write(&path, format!("#!/bin/sh\n{}\n", script)).unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    #[tokio::test]
+    async fn crashes_report_stderr_and_failures_are_forgotten() {
+        let dir = TempDir::new().unwrap();
+        let backend = SteamCmd::new(
+            steamcmd(
+                &dir,
+                "echo 'Loading Steam API...OK'\necho 'Segmentation fault' >&2\nexit 139",
+            ),
+            None,
+        );
+        let progress = Progress::new(1);
+        let bar = progress.download("Mod");
+
+        let ticket = backend.request(1, 281990, &bar).await.unwrap();
+        match backend.status(&ticket).await.unwrap() {
+            Status::Failed(error) => assert!(error.contains("Segmentation fault"), "{}", error),
+            _ => panic!("the crash was not reported"),
+        }
+        assert!(backend.results.lock().unwrap().is_empty());
+        bar.finish("failed");
+        progress.finish();
+    }
+}
@@ -0,0 +1,134 @@
+//! Downloads from steamworkshopdownloader.io.
+
+use std::collections::HashMap;
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+
+use super::{DownloadBackend, Fetch, Status, Ticket};
+use crate::progress::DownloadProgress;
+
+/// Backend for steamworkshopdownloader.io and mirrors with the same API.
+pub struct SteamWorkshopDownloader {
+    /// The `reqwest::Client`.
+    client: Client,
+    /// Address of the service, without a trailing slash.
+    base_url: String,
+}
+
+impl SteamWorkshopDownloader {
+    /// Instanciates a new backend for the service at `base_url`.
+    pub fn new(client: Client, base_url: String) -> Self {
+        Self {
+            client,
+            base_url: base_url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    /// Address of the official service.
+    pub fn default_base_url() -> String {
+        String::from("https://backend-01-prd.steamworkshopdownloader.io")
+    }
+}
+
+/// Used to serialize status requests.
+#[derive(Debug, Serialize)]
+struct Uuids<'a> {
+    /// This is used in status and download request.
+    uuids: [&'a str; 1],
+}
+
+#[async_trait]
+impl DownloadBackend for SteamWorkshopDownloader {
+    fn name(&self) -> &str {
+        "steamworkshopdownloader.io"
+    }
+
+    async fn request(
+        &self,
+        item_id: u64,
+        _app_id: u64,
+        _progress: &DownloadProgress,
+    ) -> Result<Ticket> {
+        /// Used to serialize the initial request.
+        #[allow(clippy::struct_excessive_bools, clippy::missing_docs_in_private_items)]
+        #[derive(Debug, Serialize)]
+        #[serde(rename_all = "camelCase")]
+        struct RequestBody {
+            published_file_id: u64,
+            collection_id: Option<u64>,
+            extract: bool,
+            hidden: bool,
+            direct: bool,
+            autodownload: bool,
+        }
+
+        /// Used to deserialize the initial response.
+        #[derive(Debug, Deserialize)]
+        struct RequestResponse {
+            /// This is used in the status and download request.
+            uuid: String,
+        }
+
+        let request_body = RequestBody {
+            published_file_id: item_id,
+            collection_id: None,
+            extract: true,
+            hidden: false,
+            direct: false,
+            autodownload: false,
+        };
+
+        let response = self
+            .client
+            .post(format!("{}/api/download/request", self.base_url))
+            .body(serde_json::to_string(&request_body)?)
+            .send()
+            .await?
+            .json::<RequestResponse>()
+            .await?;
+
+        Ok(Ticket(response.uuid))
+    }
+
+    async fn status(&self, ticket: &Ticket) -> Result<Status> {
+        /// Used to deserialize status responses.
+        #[derive(Debug, Deserialize)]
+        struct StatusResponse {
+            /// The status of the requested file.
+            status: String,
+        }
+
+        let status_response = self
+            .client
+            .post(format!("{}/api/download/status", self.base_url))
+            .body(serde_json::to_string(&Uuids { uuids: [&ticket.0] })?)
+            .send()
+            .await?
+            .json::<HashMap<String, StatusResponse>>()
+            .await?;
+
+        let status = status_response
+            .get(&ticket.0)
+            .ok_or_else(|| anyhow!("Uuid incorrect"))?
+            .status
+            .clone();
+
+        Ok(match status.as_str() {
+            "prepared" => Status::Ready,
+            "failed" | "error" => Status::Failed(status),
+            _ => Status::Pending(status),
+        })
+    }
+
+    async fn fetch(&self, ticket: &Ticket) -> Result<Fetch> {
+        Ok(Fetch::Request(Box::new(
+            self.client
+                .get(format!("{}/api/download/transmit", self.base_url))
+                .query(&[("uuid", &ticket.0)])
+                .build()?,
+        )))
+    }
+}
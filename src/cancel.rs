@@ -0,0 +1,193 @@
+//! Cancelling running operations.
+
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt,
+    future::Future,
+    sync::{Arc, Mutex},
+};
+
+use anyhow::Result;
+pub use tokio_util::sync::CancellationToken;
+
+/// Error of operations which were cancelled by the user.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cancelled;
+
+impl fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Cancelled by the user.")
+    }
+}
+
+impl Error for Cancelled {}
+
+/// Runs `future` until it finishes or `token` is cancelled.
+///
+/// A cancelled future is dropped, which deletes its temporary files and stops the programs it started.
+pub async fn or_cancelled<T>(
+    token: &CancellationToken,
+    future: impl Future<Output = Result<T>>,
+) -> Result<T> {
+    tokio::select! {
+        result = future => result,
+        () = token.cancelled() => Err(Cancelled.into()),
+    }
+}
+
+/// Whether `error` comes from an operation which was cancelled by the user.
+pub fn is_cancelled(error: &anyhow::Error) -> bool {
+    error.is::<Cancelled>()
+}
+
+/// A download of a running update.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RunningDownload {
+    /// Position of the game the mod is downloaded for.
+    pub game_selection: usize,
+    /// Id of the mod.
+    pub item_id: u64,
+    /// Name of the mod.
+    pub title: String,
+}
+
+/// Name and token of each running download by the position of its game and the id of its mod.
+type DownloadTokens = HashMap<(usize, u64), (String, CancellationToken)>;
+
+/// Tokens of the running downloads, which lets single downloads be cancelled.
+///
+/// Each download gets a child token of the operation's token, so cancelling the operation still cancels all of them.
+#[derive(Debug, Clone, Default)]
+pub struct Downloads {
+    /// Running downloads, shared by all clones so the update and the user interface see the same ones.
+    tokens: Arc<Mutex<DownloadTokens>>,
+}
+
+impl Downloads {
+    /// Registers the download of the mod `item_id` called `title` for the game at `game_selection`.
+    ///
+    /// Returns the token cancelling it, a child of `parent`.
+    pub fn start(
+        &self,
+        game_selection: usize,
+        item_id: u64,
+        title: &str,
+        parent: &CancellationToken,
+    ) -> CancellationToken {
+        let token = parent.child_token();
+        let _old = self
+            .tokens
+            .lock()
+            .expect("download tokens poisoned")
+            .insert(
+                (game_selection, item_id),
+                (title.to_string(), token.clone()),
+            );
+        token
+    }
+
+    /// Forgets the finished download of the mod `item_id` for the game at `game_selection`.
+    pub fn finish(&self, game_selection: usize, item_id: u64) {
+        let _token = self
+            .tokens
+            .lock()
+            .expect("download tokens poisoned")
+            .remove(&(game_selection, item_id));
+    }
+
+    /// Returns the running downloads, sorted by game and mod id.
+    pub fn running(&self) -> Vec<RunningDownload> {
+        let mut running: Vec<RunningDownload> = self
+            .tokens
+            .lock()
+            .expect("download tokens poisoned")
+            .iter()
+            .map(|(&(game_selection, item_id), (title, _))| RunningDownload {
+                game_selection,
+                item_id,
+                title: title.clone(),
+            })
+            .collect();
+        running.sort_unstable_by_key(|download| (download.game_selection, download.item_id));
+        running
+    }
+
+    /// Cancels the download of the mod `item_id` for the game at `game_selection`, the other downloads go on.
+    ///
+    /// Returns whether the mod was being downloaded.
+    pub fn cancel(&self, game_selection: usize, item_id: u64) -> bool {
+        match self
+            .tokens
+            .lock()
+            .expect("download tokens poisoned")
+            .get(&(game_selection, item_id))
+        {
+            Some((_, token)) => {
+                token.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the game and mod id of every running download.
+    fn running(downloads: &Downloads) -> Vec<(usize, u64)> {
+        downloads
+            .running()
+            .into_iter()
+            .map(|download| (download.game_selection, download.item_id))
+            .collect()
+    }
+
+    #[test]
+    fn single_downloads_are_cancelled_without_the_others() {
+        let parent = CancellationToken::new();
+        let downloads = Downloads::default();
+        let first = downloads.start(0, 1, "First", &parent);
+        let second = downloads.start(0, 2, "Second", &parent);
+        assert_eq!(running(&downloads), vec![(0, 1), (0, 2)]);
+        assert_eq!(downloads.running()[0].title, "First");
+
+        assert!(downloads.cancel(0, 1));
+        assert!(first.is_cancelled());
+        assert!(!second.is_cancelled());
+        assert!(!parent.is_cancelled());
+
+        downloads.finish(0, 1);
+        assert_eq!(running(&downloads), vec![(0, 2)]);
+        assert!(!downloads.cancel(0, 1));
+    }
+
+    #[test]
+    fn downloads_of_the_same_mod_for_different_games_are_kept_apart() {
+        let parent = CancellationToken::new();
+        let downloads = Downloads::default();
+        let first = downloads.start(0, 1, "Mod", &parent);
+        let second = downloads.start(1, 1, "Mod", &parent);
+        assert_eq!(running(&downloads), vec![(0, 1), (1, 1)]);
+
+        assert!(downloads.cancel(1, 1));
+        assert!(!first.is_cancelled());
+        assert!(second.is_cancelled());
+        downloads.finish(1, 1);
+        assert_eq!(running(&downloads), vec![(0, 1)]);
+    }
+
+    #[test]
+    fn cancelling_the_operation_cancels_all_downloads() {
+        let parent = CancellationToken::new();
+        let downloads = Downloads::default();
+        let first = downloads.start(0, 1, "First", &parent);
+        let second = downloads.start(1, 2, "Second", &parent);
+
+        parent.cancel();
+        assert!(first.is_cancelled());
+        assert!(second.is_cancelled());
+    }
+}
@@ -0,0 +1,702 @@
+//! Non-interactive command line manager.
+
+use anyhow::{bail, Result};
+use structopt::StructOpt;
+
+use pdx_mod_manager::{
+    conflicts::{Conflicts, FileConflict},
+    dependencies::InstallPlan,
+    discovery::KnownGame,
+    ModManager,
+};
+
+/// Prints the dependencies, unavailable dependencies and dependency cycles of an `InstallPlan`.
+pub fn print_plan(plan: &InstallPlan) {
+    let title = |item_id: u64| plan.title(item_id).unwrap_or("unknown mod").to_string();
+
+    if plan.items.len() > 1 {
+        println!(
+            "{} requires these mods, they will be installed too:",
+            title(plan.root)
+        );
+        for (item_id, info) in &plan.items {
+            if *item_id != plan.root {
+                println!("    {} {}", item_id, info.title);
+            }
+        }
+    }
+    for item_id in &plan.missing {
+        println!(
+            "Warning: dependency {} is not available in the workshop, it is left out.",
+            item_id
+        );
+    }
+    for cycle in &plan.cycles {
+        let mut names: Vec<String> = cycle.iter().map(|&item_id| title(item_id)).collect();
+        names.push(title(cycle[0]));
+        println!("Warning: dependency cycle {}", names.join(" -> "));
+    }
+}
+
+/// Prints which mod wins a `FileConflict`.
+pub fn print_file_conflict(conflict: &FileConflict) {
+    let losers = &conflict.mods[..conflict.mods.len() - 1];
+    println!(
+        "    {}: {} wins over {}",
+        conflict.path,
+        conflict.winner(),
+        losers.join(", ")
+    );
+}
+
+/// Prints how many files each pair of mods of `Conflicts` shares.
+pub fn print_conflict_summary(conflicts: &Conflicts) {
+    if conflicts.pairs.is_empty() {
+        println!("No enabled mods provide the same files.");
+    }
+    for pair in &conflicts.pairs {
+        println!(
+            "{} replaces {} files of {}.",
+            pair.winner,
+            pair.files.len(),
+            pair.loser
+        );
+    }
+}
+
+/// Formats a unix time as a UTC date, like the update time of an archived mod version.
+pub fn format_time(time: u64) -> String {
+    // Converts days since the unix epoch to a civil date, see http://howardhinnant.github.io/date_algorithms.html
+    let days = time / 86400;
+    let era = (days + 719_468) / 146_097;
+    let day_of_era = (days + 719_468) % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+
+    format!(
+        "{}-{:02}-{:02} {:02}:{:02} UTC",
+        year,
+        month,
+        day,
+        time % 86400 / 3600,
+        time % 3600 / 60
+    )
+}
+
+/// Command line arguments. Starting the program without a subcommand opens the interactive menu.
+#[derive(Debug, StructOpt)]
+#[structopt(name = "pdx_mod_manager")]
+pub struct Args {
+    /// Subcommand to run instead of the interactive menu.
+    #[structopt(subcommand)]
+    pub command: Option<Command>,
+}
+
+/// Subcommands which run without any prompts.
+#[derive(Debug, StructOpt)]
+#[structopt(about = "Installs and updates steam workshop mods for paradox games.")]
+pub enum Command {
+    /// Lists all configured games and their mods.
+    ListGames,
+    /// Lists installed games which aren't configured yet.
+    DetectGames {
+        /// Add all detected games.
+        #[structopt(long)]
+        add: bool,
+    },
+    /// Adds a game.
+    AddGame {
+        /// Name of the game, up to 30 characters long.
+        title: String,
+        /// Path to the mod folder of the game.
+        path_mods: String,
+        /// Steam app id of the game. Defaults to the app id of the known game with the same name.
+        #[structopt(long)]
+        app_id: Option<u64>,
+    },
+    /// Downloads and installs a mod and the mods it requires from the steam workshop.
+    AddMod {
+        /// Name of the game.
+        game: String,
+        /// Workshop id of the mod.
+        id: u64,
+        /// Install the mod even if it belongs to another game.
+        #[structopt(long)]
+        force: bool,
+    },
+    /// Installs the mods of a steam workshop collection and keeps them in sync on updates.
+    AddCollection {
+        /// Name of the game.
+        game: String,
+        /// Workshop id of the collection.
+        id: u64,
+        /// Workshop ids of members which should not be installed.
+        #[structopt(long)]
+        exclude: Vec<u64>,
+        /// Install the collection even if it belongs to another game.
+        #[structopt(long)]
+        force: bool,
+    },
+    /// Installs a mod from a .zip archive or folder on this computer. Updates reinstall it when it changed.
+    AddLocal {
+        /// Name of the game.
+        game: String,
+        /// Path to the .zip archive or folder.
+        path: String,
+        /// Name of the mod. Defaults to the name in its descriptor.
+        #[structopt(long)]
+        title: Option<String>,
+    },
+    /// Clones a git repository and installs the mod in it. Updates fast-forward the repository.
+    AddGit {
+        /// Name of the game.
+        game: String,
+        /// Address of the repository.
+        url: String,
+        /// Branch or tag to follow instead of the default branch.
+        #[structopt(long)]
+        reference: Option<String>,
+        /// Folder inside the repository which contains the mod.
+        #[structopt(long)]
+        subdir: Option<String>,
+        /// Name of the mod. Defaults to the name in its descriptor.
+        #[structopt(long)]
+        title: Option<String>,
+    },
+    /// Downloads and installs a mod from a .zip archive on the web. Updates download it again when the server reports a newer version.
+    AddUrl {
+        /// Name of the game.
+        game: String,
+        /// Http or https address of the archive.
+        url: String,
+        /// Expected SHA-256 hash of the archive. Downloads with another hash are rejected.
+        #[structopt(long)]
+        sha256: Option<String>,
+        /// Name of the mod. Defaults to the name in its descriptor.
+        #[structopt(long)]
+        title: Option<String>,
+    },
+    /// Updates the collections and mods of all games or of a single game.
+    Update {
+        /// Only update the mods of this game.
+        #[structopt(long)]
+        game: Option<String>,
+    },
+    /// Removes an installed mod.
+    RemoveMod {
+        /// Name of the game.
+        game: String,
+        /// Id of the mod, as shown by list-games.
+        id: u64,
+    },
+    /// Enables a mod in the game's list of enabled mods, so the game loads it.
+    Enable {
+        /// Name of the game.
+        game: String,
+        /// Id of the mod, as shown by list-games.
+        id: u64,
+    },
+    /// Disables a mod in the game's list of enabled mods, so the game doesn't load it.
+    Disable {
+        /// Name of the game.
+        game: String,
+        /// Id of the mod, as shown by list-games.
+        id: u64,
+    },
+    /// Lists the playsets of a game and their mods in load order.
+    ListPlaysets {
+        /// Name of the game.
+        game: String,
+    },
+    /// Creates a playset with the currently enabled mods and makes it the active one.
+    CreatePlayset {
+        /// Name of the game.
+        game: String,
+        /// Name of the playset, up to 30 characters long.
+        name: String,
+    },
+    /// Copies a playset into a new one.
+    ClonePlayset {
+        /// Name of the game.
+        game: String,
+        /// Name of the playset to copy.
+        source: String,
+        /// Name of the new playset, up to 30 characters long.
+        name: String,
+    },
+    /// Renames a playset.
+    RenamePlayset {
+        /// Name of the game.
+        game: String,
+        /// Name of the playset to rename.
+        source: String,
+        /// New name of the playset, up to 30 characters long.
+        name: String,
+    },
+    /// Enables exactly the mods of a playset in the game.
+    SwitchPlayset {
+        /// Name of the game.
+        game: String,
+        /// Name of the playset.
+        name: String,
+    },
+    /// Deletes a playset. The enabled mods stay as they are.
+    DeletePlayset {
+        /// Name of the game.
+        game: String,
+        /// Name of the playset.
+        name: String,
+    },
+    /// Imports the playsets of the paradox launcher. Playsets with the same name are replaced.
+    ImportLauncher {
+        /// Name of the game.
+        game: String,
+    },
+    /// Registers the installed mods in the paradox launcher and writes the playsets there.
+    ExportLauncher {
+        /// Name of the game.
+        game: String,
+    },
+    /// Lists the enabled mods of a game in load order.
+    LoadOrder {
+        /// Name of the game.
+        game: String,
+    },
+    /// Moves an enabled mod in the load order.
+    MoveMod {
+        /// Name of the game.
+        game: String,
+        /// Id of the mod, as shown by list-games.
+        id: u64,
+        /// Load the mod one position earlier.
+        #[structopt(long, conflicts_with_all = &["down", "to"])]
+        up: bool,
+        /// Load the mod one position later.
+        #[structopt(long, conflicts_with = "to")]
+        down: bool,
+        /// Position to move the mod to, as shown by load-order.
+        #[structopt(long)]
+        to: Option<usize>,
+    },
+    /// Sorts the load order so mods load after their dependencies and the mods of their load rules.
+    SortLoadOrder {
+        /// Name of the game.
+        game: String,
+    },
+    /// Adds a rule that a mod loads after another one when the load order is sorted.
+    LoadAfter {
+        /// Name of the game.
+        game: String,
+        /// Id of the mod which loads later, as shown by list-games.
+        id: u64,
+        /// Id of the mod which loads earlier, as shown by list-games.
+        after: u64,
+        /// Remove the rule instead.
+        #[structopt(long)]
+        remove: bool,
+    },
+    /// Lists the files which more than one enabled mod provides and which mod wins under the current load order.
+    Conflicts {
+        /// Name of the game.
+        game: String,
+        /// Only print how many files each pair of mods shares.
+        #[structopt(long)]
+        summary: bool,
+    },
+    /// Lists the archived earlier versions of a mod.
+    ListVersions {
+        /// Name of the game.
+        game: String,
+        /// Id of the mod, as shown by list-games.
+        id: u64,
+    },
+    /// Reinstalls the previous archived version of a mod and pins it there.
+    Rollback {
+        /// Name of the game.
+        game: String,
+        /// Id of the mod, as shown by list-games.
+        id: u64,
+    },
+    /// Pins a mod so updates leave it at its version.
+    Pin {
+        /// Name of the game.
+        game: String,
+        /// Id of the mod, as shown by list-games.
+        id: u64,
+        /// Archived version to install first, as shown by list-versions. Defaults to the installed version.
+        #[structopt(long)]
+        version: Option<u64>,
+    },
+    /// Lets updates move a pinned mod forward again.
+    Unpin {
+        /// Name of the game.
+        game: String,
+        /// Id of the mod, as shown by list-games.
+        id: u64,
+    },
+    /// Removes a game from the configuration. Installed mods are left untouched.
+    RemoveGame {
+        /// Name of the game.
+        game: String,
+    },
+}
+
+impl Command {
+    /// Runs the subcommand. Any error is returned so the process exits with a failure code.
+    pub async fn run(self, manager: &mut ModManager) -> Result<()> {
+        match self {
+            Self::ListGames => {
+                for game in manager.games() {
+                    println!("{} ({})", game.title, game.path_mods);
+                    let mut mods: Vec<_> = game.mods.values().collect();
+                    mods.sort_by_key(|item_mod| item_mod.id);
+                    for item_mod in mods {
+                        let mut line = format!("    {} {}", item_mod.id, item_mod.title);
+                        if !item_mod.source.is_workshop() {
+                            line.push_str(&format!(" ({})", item_mod.source));
+                        }
+                        if let Some(version) = &item_mod.version {
+                            line.push_str(&format!(" version {}", version));
+                        }
+                        if !item_mod.enabled {
+                            line.push_str(" (disabled)");
+                        }
+                        if item_mod.pinned {
+                            line.push_str(" (pinned)");
+                        }
+                        println!("{}", line);
+                    }
+                }
+                Ok(())
+            }
+            Self::DetectGames { add } => {
+                for found in manager.discover_games() {
+                    if add {
+                        let title = manager.add_discovered_game(&found)?;
+                        println!("Added {} ({})", title, found.path_mods.display());
+                    } else {
+                        println!(
+                            "{} ({}): {}",
+                            found.game.title,
+                            found.location,
+                            found.path_mods.display()
+                        );
+                    }
+                }
+                Ok(())
+            }
+            Self::AddGame {
+                title,
+                path_mods,
+                app_id,
+            } => {
+                let app_id = app_id.or_else(|| KnownGame::by_title(&title).map(|game| game.app_id));
+                manager.add_game(title, path_mods, app_id)?;
+                println!("Game added successfully!");
+                Ok(())
+            }
+            Self::AddMod { game, id, force } => {
+                let game_selection = manager.find_game(&game)?;
+                let plan = manager.plan_install(id).await?;
+                for (_, item_info) in &plan.items {
+                    if let Err(error) = manager.check_item(game_selection, item_info) {
+                        if !force {
+                            bail!("{} Use --force to install it anyway.", error);
+                        }
+                        println!("Warning: {}", error);
+                    }
+                }
+                print_plan(&plan);
+                manager.install_plan(game_selection, &plan).await
+            }
+            Self::AddCollection {
+                game,
+                id,
+                exclude,
+                force,
+            } => {
+                let game_selection = manager.find_game(&game)?;
+                let (collection_info, members) = manager.get_collection(id).await?;
+                if let Err(error) = manager.check_item(game_selection, &collection_info) {
+                    if !force {
+                        bail!("{} Use --force to install it anyway.", error);
+                    }
+                    println!("Warning: {}", error);
+                }
+
+                println!("Mods in {}:", collection_info.title);
+                for (item_id, info) in &members {
+                    let skipped = if exclude.contains(item_id) {
+                        " (excluded)"
+                    } else {
+                        ""
+                    };
+                    println!("    {} {}{}", item_id, info.title, skipped);
+                }
+
+                let members: Vec<u64> = members.into_iter().map(|(item_id, _)| item_id).collect();
+                let selected: Vec<u64> = members
+                    .iter()
+                    .filter(|item_id| !exclude.contains(item_id))
+                    .copied()
+                    .collect();
+                manager
+                    .install_collection(
+                        game_selection,
+                        id,
+                        collection_info.title,
+                        members,
+                        &selected,
+                    )
+                    .await
+            }
+            Self::AddLocal { game, path, title } => {
+                let game_selection = manager.find_game(&game)?;
+                let title = manager.install_local(game_selection, &path, title)?;
+                println!("{} installed!", title);
+                Ok(())
+            }
+            Self::AddGit {
+                game,
+                url,
+                reference,
+                subdir,
+                title,
+            } => {
+                let game_selection = manager.find_game(&game)?;
+                let title = manager
+                    .install_git(game_selection, &url, reference, subdir, title)
+                    .await?;
+                println!("{} installed!", title);
+                Ok(())
+            }
+            Self::AddUrl {
+                game,
+                url,
+                sha256,
+                title,
+            } => {
+                let game_selection = manager.find_game(&game)?;
+                let title = manager
+                    .install_url(game_selection, &url, sha256, title)
+                    .await?;
+                println!("{} installed!", title);
+                Ok(())
+            }
+            Self::Update { game } => {
+                let downloads = manager.downloads();
+                if let Some(game) = game {
+                    let game_selection = manager.find_game(&game)?;
+                    crate::cancel_downloads_on_input(
+                        downloads,
+                        manager.update_game_mods(game_selection),
+                    )
+                    .await
+                } else {
+                    crate::cancel_downloads_on_input(downloads, manager.update_all_mods()).await
+                }
+            }
+            Self::RemoveMod { game, id } => {
+                let game_selection = manager.find_game(&game)?;
+                manager.remove_mod(game_selection, &id.to_string())?;
+                println!("Mod removed!");
+                Ok(())
+            }
+            Self::Enable { game, id } => {
+                let game_selection = manager.find_game(&game)?;
+                manager.set_mod_enabled(game_selection, &id.to_string(), true)?;
+                println!("Mod enabled!");
+                Ok(())
+            }
+            Self::Disable { game, id } => {
+                let game_selection = manager.find_game(&game)?;
+                manager.set_mod_enabled(game_selection, &id.to_string(), false)?;
+                println!("Mod disabled!");
+                Ok(())
+            }
+            Self::ListPlaysets { game } => {
+                let game = manager.game(manager.find_game(&game)?)?;
+                for playset in &game.playsets {
+                    let active = if game.playset.as_deref() == Some(playset.name.as_str()) {
+                        " (active)"
+                    } else {
+                        ""
+                    };
+                    println!("{}{}", playset.name, active);
+                    for playset_mod in &playset.mods {
+                        let title = game
+                            .mods
+                            .get(&playset_mod.id.to_string())
+                            .map_or("unknown mod", |item_mod| item_mod.title.as_str());
+                        let disabled = if playset_mod.enabled {
+                            ""
+                        } else {
+                            " (disabled)"
+                        };
+                        println!("    {} {}{}", playset_mod.id, title, disabled);
+                    }
+                }
+                Ok(())
+            }
+            Self::CreatePlayset { game, name } => {
+                let game_selection = manager.find_game(&game)?;
+                manager.create_playset(game_selection, name)?;
+                println!("Playset created!");
+                Ok(())
+            }
+            Self::ClonePlayset { game, source, name } => {
+                let game_selection = manager.find_game(&game)?;
+                manager.clone_playset(game_selection, &source, name)?;
+                println!("Playset cloned!");
+                Ok(())
+            }
+            Self::RenamePlayset { game, source, name } => {
+                let game_selection = manager.find_game(&game)?;
+                manager.rename_playset(game_selection, &source, name)?;
+                println!("Playset renamed!");
+                Ok(())
+            }
+            Self::SwitchPlayset { game, name } => {
+                let game_selection = manager.find_game(&game)?;
+                manager.switch_playset(game_selection, &name)?;
+                println!("Switched to playset {}!", name.trim());
+                Ok(())
+            }
+            Self::DeletePlayset { game, name } => {
+                let game_selection = manager.find_game(&game)?;
+                manager.delete_playset(game_selection, &name)?;
+                println!("Playset deleted!");
+                Ok(())
+            }
+            Self::ImportLauncher { game } => {
+                let game_selection = manager.find_game(&game)?;
+                for name in manager.import_launcher_playsets(game_selection)? {
+                    println!("Imported playset {}!", name);
+                }
+                Ok(())
+            }
+            Self::ExportLauncher { game } => {
+                let game_selection = manager.find_game(&game)?;
+                manager.export_launcher_playsets(game_selection)?;
+                println!("Playsets exported to the paradox launcher!");
+                Ok(())
+            }
+            Self::LoadOrder { game } => {
+                let game_selection = manager.find_game(&game)?;
+                for (position, (entry, title)) in
+                    manager.load_order(game_selection)?.into_iter().enumerate()
+                {
+                    println!("    {} {} ({})", position + 1, title, entry);
+                }
+                Ok(())
+            }
+            Self::MoveMod {
+                game,
+                id,
+                up,
+                down,
+                to,
+            } => {
+                let game_selection = manager.find_game(&game)?;
+                let from = manager.load_order_position(game_selection, &id.to_string())?;
+                let to = match to {
+                    Some(0) => bail!("Positions start at 1!"),
+                    Some(to) => to - 1,
+                    None if up => from.saturating_sub(1),
+                    None if down => from + 1,
+                    None => bail!("Use --up, --down or --to to tell where to move the mod."),
+                };
+                manager.move_in_load_order(game_selection, from, to)?;
+                println!("Mod moved!");
+                Ok(())
+            }
+            Self::SortLoadOrder { game } => {
+                let game_selection = manager.find_game(&game)?;
+                for cycle in manager.sort_load_order(game_selection)? {
+                    println!(
+                        "Warning: load order cycle {} -> {}",
+                        cycle.join(" -> "),
+                        cycle[0]
+                    );
+                }
+                println!("Load order sorted!");
+                Ok(())
+            }
+            Self::LoadAfter {
+                game,
+                id,
+                after,
+                remove,
+            } => {
+                let game_selection = manager.find_game(&game)?;
+                if remove {
+                    manager.remove_load_rule(game_selection, &id.to_string(), after)?;
+                    println!("Load rule removed!");
+                } else {
+                    manager.add_load_rule(game_selection, &id.to_string(), after)?;
+                    println!("Load rule added, it is applied when the load order is sorted!");
+                }
+                Ok(())
+            }
+            Self::Conflicts { game, summary } => {
+                let game_selection = manager.find_game(&game)?;
+                let conflicts = manager.conflicts(game_selection)?;
+                if !summary && !conflicts.files.is_empty() {
+                    println!("Conflicting files:");
+                    for conflict in &conflicts.files {
+                        print_file_conflict(conflict);
+                    }
+                }
+                print_conflict_summary(&conflicts);
+                Ok(())
+            }
+            Self::ListVersions { game, id } => {
+                let game_selection = manager.find_game(&game)?;
+                let versions = manager.mod_versions(game_selection, &id.to_string())?;
+                if versions.is_empty() {
+                    println!("No earlier versions are archived.");
+                }
+                for time_updated in versions {
+                    println!("    {} ({})", time_updated, format_time(time_updated));
+                }
+                Ok(())
+            }
+            Self::Rollback { game, id } => {
+                let game_selection = manager.find_game(&game)?;
+                let time_updated = manager.rollback_mod(game_selection, &id.to_string())?;
+                println!(
+                    "Rolled back to the version from {} and pinned it!",
+                    format_time(time_updated)
+                );
+                Ok(())
+            }
+            Self::Pin { game, id, version } => {
+                let game_selection = manager.find_game(&game)?;
+                manager.pin_mod(game_selection, &id.to_string(), version)?;
+                println!("Mod pinned!");
+                Ok(())
+            }
+            Self::Unpin { game, id } => {
+                let game_selection = manager.find_game(&game)?;
+                manager.unpin_mod(game_selection, &id.to_string())?;
+                println!("Mod unpinned!");
+                Ok(())
+            }
+            Self::RemoveGame { game } => {
+                let game_selection = manager.find_game(&game)?;
+                manager.remove_game(game_selection)?;
+                println!("Game removed!");
+                Ok(())
+            }
+        }
+    }
+}
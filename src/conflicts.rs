@@ -0,0 +1,210 @@
+//! Finding files which are provided by more than one mod.
+//!
+//! The game loads the files of all enabled mods into one folder tree, a file of a mod loaded later
+//! replaces the file with the same path of every mod loaded before it. Paths are compared ignoring case
+//! like the game does on windows. Files at the top of a mod folder, like `descriptor.mod` or the thumbnail,
+//! aren't loaded by the game and never conflict, neither do hidden files and folders.
+
+use std::{collections::HashMap, fs, path::Path};
+
+use anyhow::Result;
+
+/// A file provided by more than one mod.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileConflict {
+    /// Path of the file relative to the mod folders, with `/` as separator.
+    pub path: String,
+    /// Names of the mods providing the file in load order, the last one wins.
+    pub mods: Vec<String>,
+}
+
+impl FileConflict {
+    /// Returns the name of the mod whose file the game uses.
+    pub fn winner(&self) -> &str {
+        self.mods.last().map_or("", String::as_str)
+    }
+}
+
+/// The files two mods both provide.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModPairConflict {
+    /// Name of the mod loaded first, whose files are replaced.
+    pub loser: String,
+    /// Name of the mod loaded later, whose files are used.
+    pub winner: String,
+    /// Paths of the files both mods provide.
+    pub files: Vec<String>,
+}
+
+/// All conflicts between a list of mods.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Conflicts {
+    /// Conflicting files, sorted by path.
+    pub files: Vec<FileConflict>,
+    /// Conflicts of every pair of mods sharing files, the pairs with the most files first.
+    pub pairs: Vec<ModPairConflict>,
+}
+
+/// Adds the paths of all files below `dir` to `files`, relative to the mod folder `prefix` stands for.
+fn list_files(dir: &Path, prefix: &str, files: &mut Vec<String>) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if name.starts_with('.') {
+            continue;
+        }
+        let path = format!("{}{}", prefix, name);
+        if entry.file_type()?.is_dir() {
+            list_files(&entry.path(), &format!("{}/", path), files)?;
+        } else if !prefix.is_empty() {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Finds the files provided by more than one of `mods`, given as name and folder in load order.
+pub fn find_conflicts(mods: &[(String, &Path)]) -> Result<Conflicts> {
+    let mut providers: HashMap<String, (String, Vec<usize>)> = HashMap::new();
+    for (index, (_, folder)) in mods.iter().enumerate() {
+        let mut files = Vec::new();
+        list_files(folder, "", &mut files)?;
+        for file in files {
+            let (_, indices) = providers
+                .entry(file.to_lowercase())
+                .or_insert_with(|| (file, Vec::new()));
+            if !indices.contains(&index) {
+                indices.push(index);
+            }
+        }
+    }
+
+    let mut conflicts: Vec<(String, Vec<usize>)> = providers
+        .into_values()
+        .filter(|(_, indices)| indices.len() > 1)
+        .collect();
+    conflicts.sort_by_cached_key(|(path, _)| path.to_lowercase());
+
+    let mut pairs: HashMap<(usize, usize), Vec<String>> = HashMap::new();
+    for (path, indices) in &conflicts {
+        for (position, &loser) in indices.iter().enumerate() {
+            for &winner in &indices[position + 1..] {
+                pairs.entry((loser, winner)).or_default().push(path.clone());
+            }
+        }
+    }
+    let mut pairs: Vec<((usize, usize), Vec<String>)> = pairs.into_iter().collect();
+    pairs.sort_by(|(a, a_files), (b, b_files)| b_files.len().cmp(&a_files.len()).then(a.cmp(b)));
+
+    let name = |index: usize| mods[index].0.clone();
+    Ok(Conflicts {
+        files: conflicts
+            .into_iter()
+            .map(|(path, indices)| FileConflict {
+                path,
+                mods: indices.into_iter().map(name).collect(),
+            })
+            .collect(),
+        pairs: pairs
+            .into_iter()
+            .map(|((loser, winner), files)| ModPairConflict {
+                loser: name(loser),
+                winner: name(winner),
+                files,
+            })
+            .collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use tempfile::TempDir;
+
+    use super::*;
+
+    /// Creates the mod folder `name` in `dir` with empty `files`.
+    fn mod_folder(dir: &TempDir, name: &str, files: &[&str]) -> PathBuf {
+        let folder = dir.path().join(name);
+        for file in files {
+            let path = folder.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "").unwrap();
+        }
+        fs::create_dir_all(&folder).unwrap();
+        folder
+    }
+
+    /// Finds the conflicts between the named mod folders `mods`, given in load order.
+    fn conflicts(mods: &[(&str, &PathBuf)]) -> Conflicts {
+        let mods: Vec<(String, &Path)> = mods
+            .iter()
+            .map(|(name, folder)| (String::from(*name), folder.as_path()))
+            .collect();
+        find_conflicts(&mods).unwrap()
+    }
+
+    #[test]
+    fn paths_are_compared_ignoring_case() {
+        let dir = TempDir::new().unwrap();
+        let first = mod_folder(&dir, "first", &["common/Buildings/a.txt"]);
+        let second = mod_folder(&dir, "second", &["common/buildings/A.txt"]);
+
+        let found = conflicts(&[("First", &first), ("Second", &second)]);
+        assert_eq!(
+            found.files,
+            vec![FileConflict {
+                path: String::from("common/Buildings/a.txt"),
+                mods: vec![String::from("First"), String::from("Second")],
+            }]
+        );
+        assert_eq!(found.files[0].winner(), "Second");
+    }
+
+    #[test]
+    fn top_level_and_hidden_files_never_conflict() {
+        let dir = TempDir::new().unwrap();
+        let files = [
+            "descriptor.mod",
+            "thumbnail.png",
+            ".git/config",
+            "common/.hidden",
+        ];
+        let first = mod_folder(&dir, "first", &files);
+        let second = mod_folder(&dir, "second", &files);
+
+        assert_eq!(
+            conflicts(&[("First", &first), ("Second", &second)]),
+            Conflicts::default()
+        );
+    }
+
+    #[test]
+    fn later_mods_win_and_pairs_are_sorted_by_shared_files() {
+        let dir = TempDir::new().unwrap();
+        let first = mod_folder(&dir, "first", &["common/a.txt", "common/b.txt"]);
+        let second = mod_folder(&dir, "second", &["common/a.txt"]);
+        let third = mod_folder(&dir, "third", &["common/a.txt", "common/b.txt"]);
+
+        let found = conflicts(&[("First", &first), ("Second", &second), ("Third", &third)]);
+        let paths: Vec<&str> = found.files.iter().map(|file| file.path.as_str()).collect();
+        assert_eq!(paths, ["common/a.txt", "common/b.txt"]);
+        assert_eq!(found.files[0].mods, ["First", "Second", "Third"]);
+        assert_eq!(found.files[1].winner(), "Third");
+
+        let pairs: Vec<(&str, &str, usize)> = found
+            .pairs
+            .iter()
+            .map(|pair| (pair.loser.as_str(), pair.winner.as_str(), pair.files.len()))
+            .collect();
+        assert_eq!(
+            pairs,
+            [
+                ("First", "Third", 2),
+                ("First", "Second", 1),
+                ("Second", "Third", 1)
+            ]
+        );
+    }
+}
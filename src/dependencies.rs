@@ -0,0 +1,247 @@
+//! Resolution of dependencies between workshop items and of the load order of mods.
+
+use std::{collections::HashMap, hash::Hash};
+
+use crate::remote::ItemInfo;
+
+/// All workshop items to install for a requested item.
+#[derive(Debug, Clone)]
+pub struct InstallPlan {
+    /// Id of the requested item.
+    pub root: u64,
+    /// Items to install with their metadata, every item comes after its dependencies and the requested item is last.
+    pub items: Vec<(u64, ItemInfo)>,
+    /// Dependency cycles, each listed in order with the last item requiring the first.
+    pub cycles: Vec<Vec<u64>>,
+    /// Dependencies which aren't available in the workshop and are left out.
+    pub missing: Vec<u64>,
+}
+
+/// Progress of the depth first search over the dependency graph.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Visit {
+    /// The item's dependencies are being visited.
+    InProgress,
+    /// The item and all its dependencies are ordered.
+    Done,
+}
+
+impl InstallPlan {
+    /// Orders the items of the dependency tree of `root`.
+    ///
+    /// `infos` has to contain the metadata of `root` and all its transitive dependencies, missing items are left out.
+    pub fn new(root: u64, mut infos: HashMap<u64, ItemInfo>) -> Self {
+        let mut visits = HashMap::new();
+        let mut path = Vec::new();
+        let mut order = Vec::new();
+        let mut cycles = Vec::new();
+        let mut missing: Vec<u64> = infos
+            .values()
+            .flat_map(ItemInfo::dependencies)
+            .filter(|item_id| !infos.contains_key(item_id))
+            .collect();
+        missing.sort_unstable();
+        missing.dedup();
+
+        visit(
+            root,
+            &|item_id| infos.get(&item_id).map(ItemInfo::dependencies),
+            &mut visits,
+            &mut path,
+            &mut order,
+            &mut cycles,
+        );
+
+        let items = order
+            .into_iter()
+            .filter_map(|item_id| infos.remove(&item_id).map(|info| (item_id, info)))
+            .collect();
+
+        Self {
+            root,
+            items,
+            cycles,
+            missing,
+        }
+    }
+
+    /// Returns the title of an item in the plan.
+    pub fn title(&self, item_id: u64) -> Option<&str> {
+        self.items
+            .iter()
+            .find(|(id, _)| *id == item_id)
+            .map(|(_, info)| info.title.as_str())
+    }
+
+    /// Returns the ids of the items in the plan which directly require `item_id`.
+    pub fn required_by(&self, item_id: u64) -> Vec<u64> {
+        self.items
+            .iter()
+            .filter(|(_, info)| info.dependencies().contains(&item_id))
+            .map(|(id, _)| *id)
+            .collect()
+    }
+}
+
+/// Sorts `items` so every item comes after the items it loads after, keeping their order otherwise.
+///
+/// `after` maps an item to the items it loads after, items which aren't in `items` are ignored.
+/// Returns the sorted items and the cycles which were broken up, each listed in order with the last item loading after the first.
+pub fn sort_load_order<T: Copy + Eq + Hash>(
+    items: &[T],
+    after: &HashMap<T, Vec<T>>,
+) -> (Vec<T>, Vec<Vec<T>>) {
+    let mut visits = HashMap::new();
+    let mut path = Vec::new();
+    let mut order = Vec::new();
+    let mut cycles = Vec::new();
+
+    let dependencies = |item: T| {
+        if items.contains(&item) {
+            Some(after.get(&item).cloned().unwrap_or_default())
+        } else {
+            None
+        }
+    };
+    for &item in items {
+        visit(
+            item,
+            &dependencies,
+            &mut visits,
+            &mut path,
+            &mut order,
+            &mut cycles,
+        );
+    }
+    (order, cycles)
+}
+
+/// Orders `item` after its dependencies, recording every cycle on the way.
+///
+/// `dependencies` returns the dependencies of an item or `None` if the item is left out.
+fn visit<T: Copy + Eq + Hash>(
+    item: T,
+    dependencies: &impl Fn(T) -> Option<Vec<T>>,
+    visits: &mut HashMap<T, Visit>,
+    path: &mut Vec<T>,
+    order: &mut Vec<T>,
+    cycles: &mut Vec<Vec<T>>,
+) {
+    match visits.get(&item) {
+        Some(Visit::Done) => return,
+        Some(Visit::InProgress) => {
+            let start = path
+                .iter()
+                .position(|&id| id == item)
+                .expect("item in progress missing from path");
+            cycles.push(path[start..].to_vec());
+            return;
+        }
+        None => {}
+    }
+
+    let item_dependencies = if let Some(item_dependencies) = dependencies(item) {
+        item_dependencies
+    } else {
+        return;
+    };
+
+    let _old = visits.insert(item, Visit::InProgress);
+    path.push(item);
+    for dependency in item_dependencies {
+        visit(dependency, dependencies, visits, path, order, cycles);
+    }
+    let _last = path.pop();
+    let _old = visits.insert(item, Visit::Done);
+    order.push(item);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::remote::ItemChild;
+
+    /// Returns the metadata of a workshop item called `title` which requires `dependencies`.
+    fn info(title: &str, dependencies: &[u64]) -> ItemInfo {
+        ItemInfo {
+            title: String::from(title),
+            time_updated: 0,
+            consumer_app_id: 281990,
+            children: dependencies
+                .iter()
+                .map(|id| ItemChild {
+                    publishedfileid: id.to_string(),
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn unavailable_dependencies_are_left_out_of_install_plans() {
+        let infos: HashMap<u64, ItemInfo> =
+            vec![(1, info("Mod", &[2, 3])), (2, info("Core", &[3]))]
+                .into_iter()
+                .collect();
+        let plan = InstallPlan::new(1, infos);
+
+        let ids: Vec<u64> = plan.items.iter().map(|(id, _)| *id).collect();
+        assert_eq!(ids, vec![2, 1]);
+        assert_eq!(plan.missing, vec![3]);
+        assert!(plan.cycles.is_empty());
+    }
+
+    /// Maps every item of `rules` to the items it loads after.
+    fn rules(rules: &[(char, &[char])]) -> HashMap<char, Vec<char>> {
+        rules
+            .iter()
+            .map(|&(item, after)| (item, after.to_vec()))
+            .collect()
+    }
+
+    #[test]
+    fn dependencies_load_first() {
+        let (order, cycles) = sort_load_order(&['a', 'b', 'c'], &rules(&[('a', &['c'])]));
+        assert_eq!(order, vec!['c', 'a', 'b']);
+        assert!(cycles.is_empty());
+    }
+
+    #[test]
+    fn order_is_kept_without_rules() {
+        let (order, cycles) = sort_load_order(&['c', 'a', 'b'], &HashMap::new());
+        assert_eq!(order, vec!['c', 'a', 'b']);
+        assert!(cycles.is_empty());
+    }
+
+    #[test]
+    fn transitive_rules_are_followed() {
+        let (order, _cycles) = sort_load_order(
+            &['a', 'b', 'c', 'd'],
+            &rules(&[('a', &['b']), ('b', &['d'])]),
+        );
+        assert_eq!(order, vec!['d', 'b', 'a', 'c']);
+    }
+
+    #[test]
+    fn items_which_are_not_sorted_are_ignored() {
+        let (order, cycles) = sort_load_order(&['a', 'b'], &rules(&[('a', &['x', 'b'])]));
+        assert_eq!(order, vec!['b', 'a']);
+        assert!(cycles.is_empty());
+    }
+
+    #[test]
+    fn cycles_are_broken_up_and_reported() {
+        let (order, cycles) = sort_load_order(
+            &['a', 'b', 'c', 'd'],
+            &rules(&[('a', &['b']), ('b', &['c']), ('c', &['a'])]),
+        );
+        assert_eq!(order, vec!['c', 'b', 'a', 'd']);
+        assert_eq!(cycles, vec![vec!['a', 'b', 'c']]);
+    }
+
+    #[test]
+    fn items_loading_after_themselves_are_cycles() {
+        let (order, cycles) = sort_load_order(&['a', 'b'], &rules(&[('b', &['b'])]));
+        assert_eq!(order, vec!['a', 'b']);
+        assert_eq!(cycles, vec![vec!['b']]);
+    }
+}
@@ -0,0 +1,302 @@
+//! Reading and writing of `.mod` descriptor files.
+//!
+//! Descriptors use paradox' clausewitz format, a list of `key=value` pairs where a value is either
+//! a string or a list of strings in braces:
+//!
+//! ```text
+//! name="Example"
+//! tags={
+//!     "Gameplay"
+//! }
+//! ```
+
+use std::{
+    fmt::{self, Display},
+    fs,
+    path::Path,
+};
+
+use anyhow::{bail, Result};
+
+/// Value of a descriptor entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Value {
+    /// A single string.
+    String(String),
+    /// A list of strings in braces.
+    List(Vec<String>),
+}
+
+/// The contents of a `.mod` file.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Descriptor {
+    /// Name of the mod.
+    pub name: Option<String>,
+    /// Version of the mod.
+    pub version: Option<String>,
+    /// Game version the mod was made for, may contain `*` wildcards.
+    pub supported_version: Option<String>,
+    /// Tags of the mod.
+    pub tags: Vec<String>,
+    /// Names of the mods this mod depends on.
+    pub dependencies: Vec<String>,
+    /// Thumbnail of the mod, relative to the mod folder.
+    pub picture: Option<String>,
+    /// Id of the mod in the steam workshop.
+    pub remote_file_id: Option<String>,
+    /// Folder of the mod, only used in the `.mod` file next to the mod folder.
+    pub path: Option<String>,
+    /// Zip archive of the mod, used instead of `path` by older mods.
+    pub archive: Option<String>,
+    /// All other entries in the order they were read.
+    pub other: Vec<(String, Value)>,
+}
+
+/// Tokens of the clausewitz format.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    /// A quoted or bare string.
+    Text(String),
+    /// `=`
+    Equals,
+    /// `{`
+    Open,
+    /// `}`
+    Close,
+}
+
+/// Splits a descriptor into tokens, skipping whitespace and `#` comments.
+fn tokenize(input: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '=' => tokens.push(Token::Equals),
+            '{' => tokens.push(Token::Open),
+            '}' => tokens.push(Token::Close),
+            '#' => {
+                while chars.peek().is_some_and(|&c| c != '\n') {
+                    let _comment = chars.next();
+                }
+            }
+            '"' => {
+                let mut text = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        // Only quotes and backslashes are escaped, other backslashes are part of windows paths.
+                        Some('\\') => match chars.peek() {
+                            Some(&escaped) if escaped == '"' || escaped == '\\' => {
+                                text.push(escaped);
+                                let _escaped = chars.next();
+                            }
+                            _ => text.push('\\'),
+                        },
+                        Some(c) => text.push(c),
+                        None => bail!("Unterminated string in descriptor!"),
+                    }
+                }
+                tokens.push(Token::Text(text));
+            }
+            c if c.is_whitespace() || c == '\u{feff}' => {}
+            c => {
+                let mut text = c.to_string();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || "={}\"#".contains(c) {
+                        break;
+                    }
+                    text.push(c);
+                    let _next = chars.next();
+                }
+                tokens.push(Token::Text(text));
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+impl Descriptor {
+    /// Reads and parses a `.mod` file. Files which aren't valid utf-8 are read lossily.
+    pub fn read(path: &Path) -> Result<Self> {
+        Self::parse(&String::from_utf8_lossy(&fs::read(path)?))
+    }
+
+    /// Parses the contents of a `.mod` file.
+    pub fn parse(input: &str) -> Result<Self> {
+        let mut descriptor = Self::default();
+        let mut tokens = tokenize(input)?.into_iter();
+
+        while let Some(token) = tokens.next() {
+            let key = match token {
+                Token::Text(key) => key,
+                other => bail!("Expected a key in descriptor, found {:?}!", other),
+            };
+            if tokens.next() != Some(Token::Equals) {
+                bail!("Expected = after {} in descriptor!", key);
+            }
+            let value = match tokens.next() {
+                Some(Token::Text(text)) => Value::String(text),
+                Some(Token::Open) => {
+                    let mut list = Vec::new();
+                    loop {
+                        match tokens.next() {
+                            Some(Token::Text(text)) => list.push(text),
+                            Some(Token::Close) => break,
+                            _ => bail!("Unterminated list {} in descriptor!", key),
+                        }
+                    }
+                    Value::List(list)
+                }
+                _ => bail!("Missing value for {} in descriptor!", key),
+            };
+            descriptor.set(key, value);
+        }
+        Ok(descriptor)
+    }
+
+    /// Sets the entry `key`, keeping unknown keys in `other`.
+    fn set(&mut self, key: String, value: Value) {
+        match (key.as_str(), value) {
+            ("name", Value::String(value)) => self.name = Some(value),
+            ("version", Value::String(value)) => self.version = Some(value),
+            ("supported_version", Value::String(value)) => self.supported_version = Some(value),
+            ("tags", Value::List(value)) => self.tags = value,
+            ("dependencies", Value::List(value)) => self.dependencies = value,
+            ("picture", Value::String(value)) => self.picture = Some(value),
+            ("remote_file_id", Value::String(value)) => self.remote_file_id = Some(value),
+            ("path", Value::String(value)) => self.path = Some(value),
+            ("archive", Value::String(value)) => self.archive = Some(value),
+            (_, value) => self.other.push((key, value)),
+        }
+    }
+}
+
+/// Quotes a string for the clausewitz format.
+///
+/// Backslashes are only escaped where they would be read as an escape, so windows paths stay readable for the game.
+fn quote(text: &str) -> String {
+    let mut quoted = String::from("\"");
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' if matches!(chars.peek(), None | Some('"') | Some('\\')) => {
+                quoted.push_str("\\\\")
+            }
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Writes a single entry.
+fn write_entry(f: &mut fmt::Formatter<'_>, key: &str, value: &Value) -> fmt::Result {
+    match value {
+        Value::String(text) => writeln!(f, "{}={}", key, quote(text)),
+        Value::List(list) => {
+            writeln!(f, "{}={{", key)?;
+            for text in list {
+                writeln!(f, "\t{}", quote(text))?;
+            }
+            writeln!(f, "}}")
+        }
+    }
+}
+
+/// Writes a string entry if it is set.
+fn write_string(f: &mut fmt::Formatter<'_>, key: &str, value: &Option<String>) -> fmt::Result {
+    value.as_ref().map_or(Ok(()), |value| {
+        write_entry(f, key, &Value::String(value.clone()))
+    })
+}
+
+/// Writes a list entry if it isn't empty.
+fn write_list(f: &mut fmt::Formatter<'_>, key: &str, list: &[String]) -> fmt::Result {
+    if list.is_empty() {
+        Ok(())
+    } else {
+        write_entry(f, key, &Value::List(list.to_vec()))
+    }
+}
+
+impl Display for Descriptor {
+    /// Writes the descriptor in the same layout as the paradox launcher.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_string(f, "version", &self.version)?;
+        write_list(f, "tags", &self.tags)?;
+        write_string(f, "name", &self.name)?;
+        write_string(f, "picture", &self.picture)?;
+        write_list(f, "dependencies", &self.dependencies)?;
+        write_string(f, "supported_version", &self.supported_version)?;
+        write_string(f, "path", &self.path)?;
+        write_string(f, "archive", &self.archive)?;
+        write_string(f, "remote_file_id", &self.remote_file_id)?;
+        for (key, value) in &self.other {
+            write_entry(f, key, value)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Descriptor, Value};
+
+    #[test]
+    fn parses_all_entries() {
+        let descriptor = Descriptor::parse(
+            "\u{feff}version=\"1.2\"\n# comment\ntags={\n\t\"Gameplay\"\n\t\"Fixes\"\n}\nname=Example\ndependencies={ \"Base\" }\nuser_dir=\"example\"\n",
+        )
+        .unwrap();
+        assert_eq!(descriptor.version.as_deref(), Some("1.2"));
+        assert_eq!(descriptor.tags, ["Gameplay", "Fixes"]);
+        assert_eq!(descriptor.name.as_deref(), Some("Example"));
+        assert_eq!(descriptor.dependencies, ["Base"]);
+        assert_eq!(
+            descriptor.other,
+            [("user_dir".to_string(), Value::String("example".to_string()))]
+        );
+    }
+
+    #[test]
+    fn keeps_backslashes_of_windows_paths() {
+        let descriptor = Descriptor::parse(
+            r#"archive="C:\Users\x\a.zip"
+picture="thumb\\nail.png"
+name="Say \"hi\""
+"#,
+        )
+        .unwrap();
+        assert_eq!(descriptor.archive.as_deref(), Some(r"C:\Users\x\a.zip"));
+        assert_eq!(descriptor.picture.as_deref(), Some(r"thumb\nail.png"));
+        assert_eq!(descriptor.name.as_deref(), Some(r#"Say "hi""#));
+    }
+
+    #[test]
+    fn round_trips() {
+        let descriptor = Descriptor {
+            name: Some(r#"Quote " and trailing \"#.to_string()),
+            version: Some("3.*".to_string()),
+            tags: vec!["Gameplay".to_string()],
+            dependencies: vec!["Base".to_string(), r"Double \\ slash".to_string()],
+            path: Some(r"C:\Users\x\Documents\mod\123".to_string()),
+            other: vec![(
+                "replace_path".to_string(),
+                Value::String(r"common\names".to_string()),
+            )],
+            ..Descriptor::default()
+        };
+        let written = descriptor.to_string();
+        assert!(written.contains(r#"path="C:\Users\x\Documents\mod\123""#));
+        assert_eq!(Descriptor::parse(&written).unwrap(), descriptor);
+    }
+
+    #[test]
+    fn rejects_broken_descriptors() {
+        assert!(Descriptor::parse("name=\"open").is_err());
+        assert!(Descriptor::parse("tags={ \"a\"").is_err());
+        assert!(Descriptor::parse("name").is_err());
+    }
+}
@@ -0,0 +1,412 @@
+//! Detection of installed paradox games and their mod folders.
+
+use std::{
+    env, fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use directories::{BaseDirs, UserDirs};
+
+/// A paradox game this program knows how to find.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KnownGame {
+    /// Name of the game.
+    pub title: &'static str,
+    /// Short name of the game.
+    pub abbreviation: &'static str,
+    /// Steam app id of the game, workshop items of the game carry it as `consumer_app_id`.
+    pub app_id: u64,
+    /// Name of the game's folder inside the `Paradox Interactive` user directory.
+    pub user_folder: &'static str,
+    /// Names of the install folder used by GOG.
+    pub gog_folders: &'static [&'static str],
+}
+
+/// All games that are detected automatically.
+pub const KNOWN_GAMES: &[KnownGame] = &[
+    KnownGame {
+        title: "Stellaris",
+        abbreviation: "Stellaris",
+        app_id: 281990,
+        user_folder: "Stellaris",
+        gog_folders: &["Stellaris"],
+    },
+    KnownGame {
+        title: "Hearts of Iron IV",
+        abbreviation: "HOI4",
+        app_id: 394360,
+        user_folder: "Hearts of Iron IV",
+        gog_folders: &["Hearts of Iron IV"],
+    },
+    KnownGame {
+        title: "Europa Universalis IV",
+        abbreviation: "EU4",
+        app_id: 236850,
+        user_folder: "Europa Universalis IV",
+        gog_folders: &["Europa Universalis IV"],
+    },
+    KnownGame {
+        title: "Crusader Kings III",
+        abbreviation: "CK3",
+        app_id: 1158310,
+        user_folder: "Crusader Kings III",
+        gog_folders: &["Crusader Kings III"],
+    },
+    KnownGame {
+        title: "Imperator: Rome",
+        abbreviation: "Imperator",
+        app_id: 859580,
+        user_folder: "Imperator",
+        gog_folders: &["Imperator Rome", "Imperator: Rome"],
+    },
+    KnownGame {
+        title: "Victoria 3",
+        abbreviation: "Vic3",
+        app_id: 529340,
+        user_folder: "Victoria 3",
+        gog_folders: &["Victoria 3"],
+    },
+];
+
+impl KnownGame {
+    /// Finds a known game by its name or short name, ignoring case.
+    pub fn by_title(title: &str) -> Option<Self> {
+        let title = title.trim();
+        KNOWN_GAMES
+            .iter()
+            .find(|game| {
+                game.title.eq_ignore_ascii_case(title)
+                    || game.abbreviation.eq_ignore_ascii_case(title)
+            })
+            .copied()
+    }
+
+    /// Finds a known game by its steam app id.
+    pub fn by_app_id(app_id: u64) -> Option<Self> {
+        KNOWN_GAMES
+            .iter()
+            .find(|game| game.app_id == app_id)
+            .copied()
+    }
+}
+
+/// Where a game was found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Location {
+    /// The native user directory, `~/.local/share/Paradox Interactive` on linux.
+    Native,
+    /// The documents folder, used by the windows versions.
+    Documents,
+    /// A GOG install folder, mods go into the native user directory.
+    Gog,
+    /// A wine prefix.
+    Wine,
+    /// A proton prefix in steam's `compatdata` folder.
+    Proton,
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Native => "native",
+            Self::Documents => "documents",
+            Self::Gog => "GOG",
+            Self::Wine => "wine",
+            Self::Proton => "proton",
+        })
+    }
+}
+
+/// A game found on this computer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiscoveredGame {
+    /// The game that was found.
+    pub game: KnownGame,
+    /// Where the game was found.
+    pub location: Location,
+    /// Path of the mod folder. It may not exist yet if the game was never started with mods.
+    pub path_mods: PathBuf,
+}
+
+/// Folders the scan for games starts from.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct ScanRoots {
+    /// The user's data folder, `~/.local/share` on linux.
+    data_dir: Option<PathBuf>,
+    /// The user's home folder.
+    home: Option<PathBuf>,
+    /// The user's documents folder.
+    documents: Option<PathBuf>,
+    /// The wine prefix from `WINEPREFIX`.
+    wine_prefix: Option<PathBuf>,
+}
+
+/// Scans the usual install and user directories for known games.
+///
+/// Every mod folder is only reported once, even if it was found through several locations.
+pub fn discover_games() -> Vec<DiscoveredGame> {
+    let base_dirs = BaseDirs::new();
+    let user_dirs = UserDirs::new();
+    discover_in(&ScanRoots {
+        data_dir: base_dirs
+            .as_ref()
+            .map(|base_dirs| base_dirs.data_dir().to_path_buf()),
+        home: base_dirs
+            .as_ref()
+            .map(|base_dirs| base_dirs.home_dir().to_path_buf()),
+        documents: user_dirs
+            .as_ref()
+            .and_then(UserDirs::document_dir)
+            .map(Path::to_path_buf),
+        wine_prefix: env::var_os("WINEPREFIX").map(PathBuf::from),
+    })
+}
+
+/// Scans the install and user directories below `roots` for known games.
+fn discover_in(roots: &ScanRoots) -> Vec<DiscoveredGame> {
+    let mut found = Vec::new();
+
+    // Native versions and steam versions running without proton.
+    if let Some(data_dir) = &roots.data_dir {
+        let user_root = data_dir.join("Paradox Interactive");
+        scan_user_root(&user_root, Location::Native, &mut found);
+
+        // GOG installs keep their mods in the native user directory, which only exists after the first start.
+        if let Some(home) = &roots.home {
+            for gog_root in [home.join("GOG Games"), home.join("Games")].iter() {
+                for game in KNOWN_GAMES {
+                    if game
+                        .gog_folders
+                        .iter()
+                        .any(|folder| gog_root.join(folder).is_dir())
+                    {
+                        push_unique(
+                            &mut found,
+                            DiscoveredGame {
+                                game: *game,
+                                location: Location::Gog,
+                                path_mods: user_root.join(game.user_folder).join("mod"),
+                            },
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    if let Some(documents) = &roots.documents {
+        scan_user_root(
+            &documents.join("Paradox Interactive"),
+            Location::Documents,
+            &mut found,
+        );
+    }
+
+    if let Some(home) = &roots.home {
+        let mut wine_prefixes = vec![home.join(".wine")];
+        wine_prefixes.extend(roots.wine_prefix.clone());
+        for prefix in &wine_prefixes {
+            scan_prefix(prefix, Location::Wine, &mut found);
+        }
+
+        for steam_root in [
+            home.join(".steam/steam"),
+            home.join(".local/share/Steam"),
+            home.join(".var/app/com.valvesoftware.Steam/.local/share/Steam"),
+        ]
+        .iter()
+        {
+            for prefix in subdirectories(&steam_root.join("steamapps/compatdata")) {
+                scan_prefix(&prefix.join("pfx"), Location::Proton, &mut found);
+            }
+        }
+    }
+
+    found
+}
+
+/// Scans the users of a wine prefix for paradox user directories.
+fn scan_prefix(prefix: &Path, location: Location, found: &mut Vec<DiscoveredGame>) {
+    for user in subdirectories(&prefix.join("drive_c/users")) {
+        for documents in ["Documents", "My Documents"].iter() {
+            scan_user_root(
+                &user.join(documents).join("Paradox Interactive"),
+                location,
+                found,
+            );
+        }
+    }
+}
+
+/// Looks for the mod folders of known games inside a `Paradox Interactive` directory.
+fn scan_user_root(user_root: &Path, location: Location, found: &mut Vec<DiscoveredGame>) {
+    for game in KNOWN_GAMES {
+        let path_mods = user_root.join(game.user_folder).join("mod");
+        if path_mods.is_dir() {
+            push_unique(
+                found,
+                DiscoveredGame {
+                    game: *game,
+                    location,
+                    path_mods,
+                },
+            );
+        }
+    }
+}
+
+/// Adds `game` unless its mod folder was already found.
+fn push_unique(found: &mut Vec<DiscoveredGame>, game: DiscoveredGame) {
+    let path = canonical(&game.path_mods);
+    if !found
+        .iter()
+        .any(|other| canonical(&other.path_mods) == path)
+    {
+        found.push(game);
+    }
+}
+
+/// Resolves symlinks like `~/.steam/steam` where possible.
+pub(crate) fn canonical(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Lists the directories directly inside `path`, ignoring errors.
+fn subdirectories(path: &Path) -> Vec<PathBuf> {
+    fs::read_dir(path)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .filter(|path| path.is_dir())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+
+    use super::*;
+
+    /// Creates the folder `path` below `dir` and returns it.
+    fn folder(dir: &TempDir, path: &str) -> PathBuf {
+        let folder = dir.path().join(path);
+        fs::create_dir_all(&folder).unwrap();
+        folder
+    }
+
+    /// Returns the title, location and mod folder below `dir` of every game found.
+    fn found(dir: &TempDir, roots: &ScanRoots) -> Vec<(&'static str, Location, PathBuf)> {
+        discover_in(roots)
+            .into_iter()
+            .map(|found| {
+                (
+                    found.game.title,
+                    found.location,
+                    found
+                        .path_mods
+                        .strip_prefix(dir.path())
+                        .unwrap()
+                        .to_path_buf(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn games_are_found_in_the_documents_folder() {
+        let dir = TempDir::new().unwrap();
+        let _mods = folder(&dir, "Documents/Paradox Interactive/Stellaris/mod");
+        // Games without a mod folder were never started with mods and are left out.
+        let _settings = folder(&dir, "Documents/Paradox Interactive/Hearts of Iron IV");
+        let roots = ScanRoots {
+            documents: Some(dir.path().join("Documents")),
+            ..ScanRoots::default()
+        };
+
+        assert_eq!(
+            found(&dir, &roots),
+            vec![(
+                "Stellaris",
+                Location::Documents,
+                PathBuf::from("Documents/Paradox Interactive/Stellaris/mod")
+            )]
+        );
+    }
+
+    #[test]
+    fn games_are_found_in_proton_and_wine_prefixes() {
+        let dir = TempDir::new().unwrap();
+        let _proton = folder(
+            &dir,
+            "home/.local/share/Steam/steamapps/compatdata/281990/pfx/drive_c/users/steamuser/Documents/Paradox Interactive/Stellaris/mod",
+        );
+        let _wine = folder(
+            &dir,
+            "prefix/drive_c/users/user/My Documents/Paradox Interactive/Europa Universalis IV/mod",
+        );
+        let roots = ScanRoots {
+            home: Some(dir.path().join("home")),
+            wine_prefix: Some(dir.path().join("prefix")),
+            ..ScanRoots::default()
+        };
+
+        assert_eq!(
+            found(&dir, &roots),
+            vec![
+                (
+                    "Europa Universalis IV",
+                    Location::Wine,
+                    PathBuf::from("prefix/drive_c/users/user/My Documents/Paradox Interactive/Europa Universalis IV/mod")
+                ),
+                (
+                    "Stellaris",
+                    Location::Proton,
+                    PathBuf::from("home/.local/share/Steam/steamapps/compatdata/281990/pfx/drive_c/users/steamuser/Documents/Paradox Interactive/Stellaris/mod")
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn gog_installs_are_found_before_their_mod_folder_exists() {
+        let dir = TempDir::new().unwrap();
+        let _install = folder(&dir, "home/GOG Games/Imperator Rome");
+        let roots = ScanRoots {
+            data_dir: Some(dir.path().join("data")),
+            home: Some(dir.path().join("home")),
+            ..ScanRoots::default()
+        };
+
+        assert_eq!(
+            found(&dir, &roots),
+            vec![(
+                "Imperator: Rome",
+                Location::Gog,
+                PathBuf::from("data/Paradox Interactive/Imperator/mod")
+            )]
+        );
+    }
+
+    #[test]
+    fn mod_folders_are_only_reported_once() {
+        let dir = TempDir::new().unwrap();
+        let _mods = folder(&dir, "data/Paradox Interactive/Stellaris/mod");
+        let roots = ScanRoots {
+            data_dir: Some(dir.path().join("data")),
+            documents: Some(dir.path().join("data")),
+            ..ScanRoots::default()
+        };
+
+        assert_eq!(
+            found(&dir, &roots),
+            vec![(
+                "Stellaris",
+                Location::Native,
+                PathBuf::from("data/Paradox Interactive/Stellaris/mod")
+            )]
+        );
+    }
+}
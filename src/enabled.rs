@@ -0,0 +1,299 @@
+//! Reading and writing the list of mods a game loads.
+//!
+//! Games started by the paradox launcher read the enabled mods from `dlc_load.json`, older games from
+//! the `last_mods` entry of `settings.txt`. Both files are next to the mod folder and list the `.mod`
+//! files relative to it, like `mod/123.mod`:
+//!
+//! ```text
+//! {"enabled_mods":["mod/123.mod"],"disabled_dlcs":[]}
+//!
+//! last_mods={
+//!     "mod/123.mod"
+//! }
+//! ```
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, bail, Result};
+use serde_json::{json, Value};
+
+/// Name of the list the paradox launcher writes.
+const DLC_LOAD: &str = "dlc_load.json";
+
+/// Name of the settings file of older games.
+const SETTINGS: &str = "settings.txt";
+
+/// Entry of `settings.txt` which lists the enabled mods.
+const LAST_MODS: &str = "last_mods";
+
+/// A file listing the enabled mods of a game.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EnabledFile {
+    /// `dlc_load.json` of games started by the paradox launcher.
+    DlcLoad(PathBuf),
+    /// `settings.txt` with a `last_mods` entry of older games.
+    Settings(PathBuf),
+}
+
+/// Returns the files listing the enabled mods of the game with the mod folder `path_mods`.
+///
+/// If neither file exists yet, a `dlc_load.json` is created on the first write.
+pub fn enabled_files(path_mods: &str) -> Vec<EnabledFile> {
+    let user_dir = user_dir(path_mods);
+    let mut files = Vec::new();
+
+    let dlc_load = user_dir.join(DLC_LOAD);
+    if dlc_load.exists() {
+        files.push(EnabledFile::DlcLoad(dlc_load.clone()));
+    }
+    let settings = user_dir.join(SETTINGS);
+    if fs::read_to_string(&settings).is_ok_and(|text| find_last_mods(&text).is_some()) {
+        files.push(EnabledFile::Settings(settings));
+    }
+
+    if files.is_empty() {
+        files.push(EnabledFile::DlcLoad(dlc_load));
+    }
+    files
+}
+
+/// Returns the entry of the `.mod` file of the mod `id` in the enabled mods of the game with the mod folder `path_mods`.
+pub fn mod_entry(path_mods: &str, id: &str) -> String {
+    let folder = Path::new(path_mods.trim()).file_name().map_or_else(
+        || String::from("mod"),
+        |name| name.to_string_lossy().into_owned(),
+    );
+    format!("{}/{}.mod", folder, id)
+}
+
+/// Whether the `.mod` file of an entry exists for the game with the mod folder `path_mods`.
+pub fn entry_exists(path_mods: &str, entry: &str) -> bool {
+    user_dir(path_mods).join(entry).exists()
+}
+
+/// Returns the folder containing the mod folder `path_mods`, where the game keeps its settings.
+pub fn user_dir(path_mods: &str) -> PathBuf {
+    Path::new(path_mods.trim())
+        .parent()
+        .map_or_else(|| PathBuf::from("."), Path::to_path_buf)
+}
+
+impl EnabledFile {
+    /// Returns the path of the file.
+    pub fn path(&self) -> &Path {
+        match self {
+            Self::DlcLoad(path) | Self::Settings(path) => path,
+        }
+    }
+
+    /// Reads the enabled mods in load order. Returns `None` if the file doesn't exist.
+    pub fn read(&self) -> Result<Option<Vec<String>>> {
+        let text = match fs::read_to_string(self.path()) {
+            Ok(text) => text,
+            Err(_) if !self.path().exists() => return Ok(None),
+            Err(error) => return Err(error.into()),
+        };
+
+        Ok(Some(match self {
+            Self::DlcLoad(_) => {
+                let dlc_load: Value = serde_json::from_str(&text)?;
+                dlc_load
+                    .get("enabled_mods")
+                    .and_then(Value::as_array)
+                    .map(|mods| {
+                        mods.iter()
+                            .filter_map(Value::as_str)
+                            .map(String::from)
+                            .collect()
+                    })
+                    .unwrap_or_default()
+            }
+            Self::Settings(_) => {
+                let (_key, start, end) = find_last_mods(&text)
+                    .ok_or_else(|| anyhow!("{} has no {} entry!", SETTINGS, LAST_MODS))?;
+                text[start..end]
+                    .split('"')
+                    .skip(1)
+                    .step_by(2)
+                    .map(String::from)
+                    .collect()
+            }
+        }))
+    }
+
+    /// Replaces the enabled mods with `entries`, keeping everything else in the file.
+    pub fn write(&self, entries: &[String]) -> Result<()> {
+        let text = match self {
+            Self::DlcLoad(path) => {
+                let mut dlc_load = if path.exists() {
+                    serde_json::from_str(&fs::read_to_string(path)?)?
+                } else {
+                    json!({ "disabled_dlcs": [] })
+                };
+                match dlc_load.as_object_mut() {
+                    Some(object) => {
+                        let _old = object.insert(String::from("enabled_mods"), json!(entries));
+                    }
+                    None => bail!("{} is not a json object!", path.display()),
+                }
+                dlc_load.to_string()
+            }
+            Self::Settings(path) => {
+                let mut text = fs::read_to_string(path)?;
+                let mut last_mods = format!("{}={{\n", LAST_MODS);
+                for entry in entries {
+                    last_mods.push_str(&format!("\t\"{}\"\n", entry));
+                }
+                last_mods.push('}');
+
+                match find_last_mods(&text) {
+                    Some((key, _start, end)) => text.replace_range(key..=end, &last_mods),
+                    None => {
+                        text.push_str(&last_mods);
+                        text.push('\n');
+                    }
+                }
+                text
+            }
+        };
+
+        let partial = self.path().with_extension("tmp");
+        fs::write(&partial, text)?;
+        fs::rename(partial, self.path())?;
+        Ok(())
+    }
+}
+
+/// Finds the `last_mods` entry in a `settings.txt`.
+///
+/// Returns the position of the key, after the opening brace and of the closing brace.
+fn find_last_mods(text: &str) -> Option<(usize, usize, usize)> {
+    let mut offset = 0;
+    while let Some(found) = text[offset..].find(LAST_MODS) {
+        let key = offset + found;
+        offset = key + LAST_MODS.len();
+
+        let at_line_start = text[..key]
+            .chars()
+            .next_back()
+            .is_none_or(char::is_whitespace);
+        let rest = text[offset..].trim_start();
+        if !at_line_start || !rest.starts_with('=') {
+            continue;
+        }
+        let rest = rest[1..].trim_start();
+        if !rest.starts_with('{') {
+            continue;
+        }
+
+        let start = text.len() - rest.len() + 1;
+        return text[start..].find('}').map(|end| (key, start, start + end));
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+
+    use super::*;
+
+    /// Writes `text` to `name` in `dir` and returns the path.
+    fn file(dir: &TempDir, name: &str, text: &str) -> PathBuf {
+        let path = dir.path().join(name);
+        fs::write(&path, text).unwrap();
+        path
+    }
+
+    /// Returns `names` as owned list entries.
+    fn entries(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| String::from(*name)).collect()
+    }
+
+    #[test]
+    fn empty_last_mods_are_read_and_written() {
+        let dir = TempDir::new().unwrap();
+        let settings = EnabledFile::Settings(file(&dir, SETTINGS, "size=10\nlast_mods={}\n"));
+        assert_eq!(settings.read().unwrap(), Some(Vec::new()));
+
+        settings.write(&entries(&["mod/1.mod"])).unwrap();
+        assert_eq!(
+            fs::read_to_string(settings.path()).unwrap(),
+            "size=10\nlast_mods={\n\t\"mod/1.mod\"\n}\n"
+        );
+        settings.write(&[]).unwrap();
+        assert_eq!(settings.read().unwrap(), Some(Vec::new()));
+    }
+
+    #[test]
+    fn keys_containing_last_mods_are_skipped() {
+        let dir = TempDir::new().unwrap();
+        let text = "other_last_mods={\n\t\"mod/9.mod\"\n}\nlast_mods={\n\t\"mod/1.mod\"\n}\n";
+        let settings = EnabledFile::Settings(file(&dir, SETTINGS, text));
+        assert_eq!(settings.read().unwrap(), Some(entries(&["mod/1.mod"])));
+
+        settings.write(&entries(&["mod/2.mod"])).unwrap();
+        assert_eq!(
+            fs::read_to_string(settings.path()).unwrap(),
+            "other_last_mods={\n\t\"mod/9.mod\"\n}\nlast_mods={\n\t\"mod/2.mod\"\n}\n"
+        );
+    }
+
+    #[test]
+    fn missing_last_mods_entries_are_appended() {
+        let dir = TempDir::new().unwrap();
+        let settings = EnabledFile::Settings(file(&dir, SETTINGS, "size=10\n"));
+        assert!(settings.read().is_err());
+
+        settings.write(&entries(&["mod/1.mod"])).unwrap();
+        assert_eq!(
+            fs::read_to_string(settings.path()).unwrap(),
+            "size=10\nlast_mods={\n\t\"mod/1.mod\"\n}\n"
+        );
+        assert_eq!(settings.read().unwrap(), Some(entries(&["mod/1.mod"])));
+    }
+
+    #[test]
+    fn settings_without_last_mods_are_not_used() {
+        let dir = TempDir::new().unwrap();
+        let _settings = file(&dir, SETTINGS, "size=10\n");
+        let path_mods = dir.path().join("mod");
+        assert_eq!(
+            enabled_files(path_mods.to_str().unwrap()),
+            vec![EnabledFile::DlcLoad(dir.path().join(DLC_LOAD))]
+        );
+    }
+
+    #[test]
+    fn other_fields_of_dlc_load_are_kept() {
+        let dir = TempDir::new().unwrap();
+        let text = r#"{"enabled_mods":["mod/1.mod"],"disabled_dlcs":["dlc/001.dlc"],"enabled_ugc":[],"version":2}"#;
+        let dlc_load = EnabledFile::DlcLoad(file(&dir, DLC_LOAD, text));
+        assert_eq!(dlc_load.read().unwrap(), Some(entries(&["mod/1.mod"])));
+
+        dlc_load
+            .write(&entries(&["mod/2.mod", "mod/1.mod"]))
+            .unwrap();
+        let written: Value =
+            serde_json::from_str(&fs::read_to_string(dlc_load.path()).unwrap()).unwrap();
+        assert_eq!(
+            written,
+            json!({
+                "enabled_mods": ["mod/2.mod", "mod/1.mod"],
+                "disabled_dlcs": ["dlc/001.dlc"],
+                "enabled_ugc": [],
+                "version": 2,
+            })
+        );
+    }
+
+    #[test]
+    fn missing_files_are_not_an_error() {
+        let dir = TempDir::new().unwrap();
+        let dlc_load = EnabledFile::DlcLoad(dir.path().join(DLC_LOAD));
+        assert_eq!(dlc_load.read().unwrap(), None);
+    }
+}
@@ -1,65 +1,494 @@
 //! Filesystem functionality.
 
 use std::{
-    fs::{self, OpenOptions},
-    io::{Cursor, Write},
-    path::Path,
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
 };
 
-use crate::Config;
-use anyhow::Result;
-use bytes::Bytes;
-use zip::ZipArchive;
+use crate::{
+    backend::Download,
+    descriptor::Descriptor,
+    progress::Printer,
+    source::{fnv1a, Source},
+    Config,
+};
+use anyhow::{anyhow, bail, Result};
+use zip::{write::FileOptions, CompressionMethod, ZipArchive, ZipWriter};
+
+/// Config file the program uses, in the current directory.
+pub const CONFIG_FILE: &str = "config.toml";
 
+/// Extracts or copies a downloaded mod into `path_mods` and writes its `.mod` file, replacing any older version.
+///
+/// The mod is staged in `path_mods/.staging` first and only swapped in once it was extracted completely.
+/// The older version is kept until the swap succeeded and restored if anything fails.
+/// Messages are printed with `printer`. Returns the descriptor written to the `.mod` file.
 pub fn write_mod(
     id: u64,
     title: String,
-    mut zip: ZipArchive<Cursor<Bytes>>,
+    source: &Source,
+    download: Download,
     path_mods: &str,
+    printer: &Printer,
+) -> Result<Descriptor> {
+    let install_path = Path::new(path_mods.trim()).join(id.to_string());
+    let mod_file_path = Path::new(path_mods.trim()).join(format!("{}.mod", id));
+    let staging = staging_path(path_mods, id);
+    fs::create_dir_all(&staging)?;
+    recover_backup(&staging, &install_path, &mod_file_path)?;
+
+    let staged = stage_mod(id, &title, source, download, &staging, printer);
+    let descriptor = match staged {
+        Ok(descriptor) => descriptor,
+        Err(error) => {
+            let _removed = fs::remove_dir_all(&staging);
+            return Err(error);
+        }
+    };
+
+    printer.println("Replacing old version.");
+    swap_in(&staging, &install_path, &mod_file_path, printer)?;
+    fs::remove_dir_all(&staging)?;
+    Ok(descriptor)
+}
+
+/// Returns the folder downloads for the game with the mod folder `path_mods` are written to, creating it if needed.
+///
+/// Downloads stay on the same disk as the mod folder instead of the system's temporary folder, which may be kept in memory.
+pub fn download_path(path_mods: &str) -> Result<PathBuf> {
+    let path = Path::new(path_mods.trim()).join(".staging");
+    fs::create_dir_all(&path)?;
+    Ok(path)
+}
+
+/// Returns the folder a new version of the mod `id` is prepared in before it replaces the installed one.
+pub fn staging_path(path_mods: &str, id: u64) -> PathBuf {
+    Path::new(path_mods.trim())
+        .join(".staging")
+        .join(id.to_string())
+}
+
+/// Extracts or copies a downloaded mod into `staging/mod` and writes its `.mod` file to `staging/mod.mod`.
+fn stage_mod(
+    id: u64,
+    title: &str,
+    source: &Source,
+    download: Download,
+    staging: &Path,
+    printer: &Printer,
+) -> Result<Descriptor> {
+    let mod_path = staging.join("mod");
+    if mod_path.exists() {
+        fs::remove_dir_all(&mod_path)?;
+    }
+
+    match download {
+        Download::Archive(file) => ZipArchive::new(file.reopen()?)?.extract(&mod_path)?,
+        Download::LocalArchive(path) => ZipArchive::new(File::open(path)?)?.extract(&mod_path)?,
+        Download::Directory(source) => copy_dir(&source, &mod_path)?,
+    }
+    if fs::read_dir(&mod_path)?.next().is_none() {
+        bail!("The download of {} is empty!", title.trim());
+    }
+
+    printer.println("Writing .mod file.");
+    let descriptor_path = mod_path.join("descriptor.mod");
+    let mut descriptor = if descriptor_path.exists() {
+        Descriptor::read(&descriptor_path).unwrap_or_else(|error| {
+            printer.println(format!(
+                "Could not read descriptor.mod of {}: {}",
+                title.trim(),
+                error
+            ));
+            Descriptor::default()
+        })
+    } else {
+        Descriptor::default()
+    };
+    if descriptor.name.is_none() {
+        descriptor.name = Some(title.trim().to_string());
+    }
+    descriptor.path = Some(format!("mod/{}", id));
+    descriptor.archive = None;
+    descriptor.remote_file_id = if source.is_workshop() {
+        Some(id.to_string())
+    } else {
+        None
+    };
+
+    fs::write(staging.join("mod.mod"), descriptor.to_string())?;
+    Ok(descriptor)
+}
+
+/// Moves the staged mod in `staging` to `install_path` and `mod_file_path`.
+///
+/// The installed version is moved to `staging/old` and `staging/old.mod` first and moved back if the swap fails.
+/// Once the new version is in place `staging/swapped` records that the swap finished and the old version is deleted.
+fn swap_in(
+    staging: &Path,
+    install_path: &Path,
+    mod_file_path: &Path,
+    printer: &Printer,
 ) -> Result<()> {
-    let install_path = format!("{}/{}", path_mods.trim(), id);
-    let mod_file_path = format!("{}/{}.mod", path_mods.trim(), id);
-    if Path::new(&install_path).exists() {
-        println!("Deleting old mod folder.");
-        fs::remove_dir_all(&install_path)?;
-    }
-    if Path::new(&mod_file_path).exists() {
-        println!("Deleting old .mod file.");
-        fs::remove_file(&mod_file_path)?;
-    }
-
-    zip.extract(install_path)?;
-
-    println!("Writing .mod file.");
-    let mut mod_file = OpenOptions::new()
-        .read(true)
-        .write(true)
-        .create(true)
-        .truncate(true)
-        .open(mod_file_path)?;
-
-    mod_file.write_all(
-        format!(
-            "name=\"{}\"\npath=\"{}\"",
-            title.trim(),
-            format!("mod/{}", id)
-        )
-        .as_bytes(),
-    )?;
+    let old_path = staging.join("old");
+    let old_mod_file = staging.join("old.mod");
+
+    let swapped = (|| -> Result<()> {
+        if install_path.exists() {
+            fs::rename(install_path, &old_path)?;
+        }
+        if mod_file_path.exists() {
+            fs::rename(mod_file_path, &old_mod_file)?;
+        }
+        fs::rename(staging.join("mod"), install_path)?;
+        fs::rename(staging.join("mod.mod"), mod_file_path)?;
+        File::create(staging.join("swapped"))?.sync_all()?;
+        Ok(())
+    })();
+
+    if let Err(error) = swapped {
+        printer.println("Restoring old version.");
+        recover_backup(staging, install_path, mod_file_path)?;
+        return Err(error);
+    }
+    remove_backup(staging)
+}
+
+/// Deletes the older version a finished swap left in `staging`, the marker of the finished swap last.
+fn remove_backup(staging: &Path) -> Result<()> {
+    let old_path = staging.join("old");
+    let old_mod_file = staging.join("old.mod");
+    if old_path.exists() {
+        fs::remove_dir_all(&old_path)?;
+    }
+    if old_mod_file.exists() {
+        fs::remove_file(&old_mod_file)?;
+    }
+    fs::remove_file(staging.join("swapped"))?;
+    Ok(())
+}
+
+/// Moves an older version left in `staging` by an interrupted swap back to `install_path` and `mod_file_path`.
+///
+/// If the swap had finished, which `staging/swapped` records, the older version is deleted instead.
+fn recover_backup(staging: &Path, install_path: &Path, mod_file_path: &Path) -> Result<()> {
+    if staging.join("swapped").exists() {
+        return remove_backup(staging);
+    }
+
+    let old_path = staging.join("old");
+    let old_mod_file = staging.join("old.mod");
+
+    if old_path.exists() {
+        if install_path.exists() {
+            fs::remove_dir_all(install_path)?;
+        }
+        fs::rename(&old_path, install_path)?;
+    }
+    if old_mod_file.exists() {
+        if mod_file_path.exists() {
+            fs::remove_file(mod_file_path)?;
+        }
+        fs::rename(&old_mod_file, mod_file_path)?;
+    }
+    Ok(())
+}
+
+/// Reads a mod from this computer, either a `.zip` archive or a folder.
+pub fn read_local(path: &Path) -> Result<Download> {
+    if path.is_dir() {
+        Ok(Download::Directory(path.to_path_buf()))
+    } else if path
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("zip"))
+    {
+        Ok(Download::LocalArchive(path.to_path_buf()))
+    } else if path.exists() {
+        bail!("{} is neither a .zip archive nor a folder!", path.display())
+    } else {
+        bail!("{} does not exist!", path.display())
+    }
+}
+
+/// Returns the newest modification time of `path` and everything inside it in unix time.
+pub fn modified_time(path: &Path) -> Result<u64> {
+    let metadata = fs::metadata(path)?;
+    let mut newest = metadata
+        .modified()?
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_secs());
+    if metadata.is_dir() {
+        for entry in fs::read_dir(path)? {
+            newest = newest.max(modified_time(&entry?.path())?);
+        }
+    }
+    Ok(newest)
+}
+
+/// Returns the folder the git repository of the mod `id` is cloned into.
+pub fn repository_path(path_mods: &str, id: u64) -> PathBuf {
+    Path::new(path_mods.trim())
+        .join(".repositories")
+        .join(id.to_string())
+}
+
+/// Returns the folder the program keeps its caches in.
+pub fn cache_dir() -> Option<PathBuf> {
+    directories::ProjectDirs::from("", "", "pdx_mod_manager")
+        .map(|dirs| dirs.cache_dir().to_path_buf())
+}
+
+/// Returns the folder below `cache_dir` the archived earlier versions of the mod `id` are kept in.
+///
+/// The archives are separated by games through a hash of their mod folder `path_mods`.
+pub fn versions_path(cache_dir: &Path, path_mods: &str, id: u64) -> PathBuf {
+    cache_dir
+        .join("versions")
+        .join(format!("{:016x}", fnv1a(path_mods.trim().as_bytes())))
+        .join(id.to_string())
+}
+
+/// Returns the archive of the version of the mod `id` which was updated at `time_updated`.
+pub fn version_archive(cache_dir: &Path, path_mods: &str, id: u64, time_updated: u64) -> PathBuf {
+    versions_path(cache_dir, path_mods, id).join(format!("{}.zip", time_updated))
+}
+
+/// Returns the update times of all archived versions of the mod `id`, the newest first.
+pub fn archived_versions(cache_dir: &Path, path_mods: &str, id: u64) -> Result<Vec<u64>> {
+    let folder = versions_path(cache_dir, path_mods, id);
+    if !folder.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut versions: Vec<u64> = Vec::new();
+    for entry in fs::read_dir(folder)? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|extension| extension == "zip") {
+            if let Some(time_updated) = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .and_then(|stem| stem.parse().ok())
+            {
+                versions.push(time_updated);
+            }
+        }
+    }
+    versions.sort_unstable_by(|a, b| b.cmp(a));
+    Ok(versions)
+}
+
+/// Compresses the installed folder of the mod `id` into its archive for `time_updated`.
+///
+/// Only the newest `kept` archives are kept, older ones are deleted.
+pub fn archive_version(
+    cache_dir: &Path,
+    path_mods: &str,
+    id: u64,
+    time_updated: u64,
+    kept: usize,
+) -> Result<()> {
+    let install_path = Path::new(path_mods.trim()).join(id.to_string());
+    if kept > 0 && install_path.is_dir() {
+        let archive = version_archive(cache_dir, path_mods, id, time_updated);
+        let partial = archive.with_extension("zip.tmp");
+        fs::create_dir_all(versions_path(cache_dir, path_mods, id))?;
+
+        let mut writer = ZipWriter::new(File::create(&partial)?);
+        zip_dir(&mut writer, &install_path, "")?;
+        writer.finish()?.sync_all()?;
+        fs::rename(partial, archive)?;
+    }
+
+    for time_updated in archived_versions(cache_dir, path_mods, id)?
+        .into_iter()
+        .skip(kept)
+    {
+        fs::remove_file(version_archive(cache_dir, path_mods, id, time_updated))?;
+    }
+    Ok(())
+}
+
+/// Adds the contents of the directory `source` to `writer` below `prefix`.
+fn zip_dir(writer: &mut ZipWriter<File>, source: &Path, prefix: &str) -> Result<()> {
+    let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+    for entry in fs::read_dir(source)? {
+        let entry = entry?;
+        let name = format!("{}{}", prefix, entry.file_name().to_string_lossy());
+        if entry.file_type()?.is_dir() {
+            writer.add_directory(format!("{}/", name), options)?;
+            zip_dir(writer, &entry.path(), &format!("{}/", name))?;
+        } else {
+            writer.start_file(name, options)?;
+            let _bytes = io::copy(&mut File::open(entry.path())?, writer)?;
+        }
+    }
+    Ok(())
+}
+
+/// Copies the directory `source` with all its contents to `target`. Git metadata is left out.
+pub fn copy_dir(source: &Path, target: &Path) -> Result<()> {
+    fs::create_dir_all(target)?;
+    for entry in fs::read_dir(source)? {
+        let entry = entry?;
+        if entry.file_name() == ".git" {
+            continue;
+        }
+        let target = target.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else {
+            let _bytes = fs::copy(entry.path(), target)?;
+        }
+    }
     Ok(())
 }
 
 impl Config {
-    /// Updates the config file.
-    pub fn update_config_file(&self) -> Result<()> {
+    /// Reads the config file at `path`, creating an empty one if it doesn't exist yet.
+    pub fn load(path: &Path) -> Result<Self> {
+        let config = if let Ok(config) = fs::read_to_string(path) {
+            config
+        } else {
+            let mut file = File::create(path)?;
+            let new_config = String::from("games = []");
+            file.write_all(b"games = []")?;
+            new_config
+        };
+
+        Ok(toml::from_str::<Self>(&config)?)
+    }
+
+    /// Returns the position of the `Game` called `title`, ignoring case.
+    pub fn find_game(&self, title: &str) -> Result<usize> {
+        self.games
+            .iter()
+            .position(|game| game.title.eq_ignore_ascii_case(title.trim()))
+            .ok_or_else(|| anyhow!("No game named {} is configured!", title.trim()))
+    }
+
+    /// Updates the config file at `path`.
+    ///
+    /// The configuration is written to a temporary file which then replaces the config file,
+    /// so an interrupted write never leaves a broken config file behind.
+    pub fn update_config_file(&self, path: &Path) -> Result<()> {
+        let mut temporary = path.as_os_str().to_owned();
+        temporary.push(".tmp");
         let mut config_file = OpenOptions::new()
-            .read(true)
             .write(true)
             .create(true)
             .truncate(true)
-            .open("config.toml")?;
+            .open(&temporary)?;
 
         config_file.write_all(toml::to_string(&self)?.as_bytes())?;
+        config_file.sync_all()?;
+        fs::rename(&temporary, path)?;
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+
+    use super::*;
+
+    /// Writes a mod folder with `content` in `common/file.txt` below `dir` and returns it as download.
+    fn download(dir: &Path, content: &str) -> Download {
+        let folder = dir.join(content);
+        fs::create_dir_all(folder.join("common")).unwrap();
+        fs::write(folder.join("common").join("file.txt"), content).unwrap();
+        Download::Directory(folder)
+    }
+
+    /// Installs the mod 1 with `content` into `path_mods`.
+    fn install(dir: &Path, path_mods: &str, content: &str) -> Result<Descriptor> {
+        write_mod(
+            1,
+            String::from("Mod"),
+            &Source::Workshop,
+            download(dir, content),
+            path_mods,
+            &Printer::default(),
+        )
+    }
+
+    /// Returns the content of `common/file.txt` of the mod 1 installed in `path_mods`.
+    fn installed(path_mods: &Path) -> String {
+        fs::read_to_string(path_mods.join("1").join("common").join("file.txt")).unwrap()
+    }
+
+    #[test]
+    fn finished_swaps_leave_nothing_behind() {
+        let dir = TempDir::new().unwrap();
+        let path_mods = dir.path().join("mod");
+        let mods = path_mods.to_str().unwrap();
+        install(dir.path(), mods, "first").unwrap();
+        install(dir.path(), mods, "second").unwrap();
+
+        assert_eq!(installed(&path_mods), "second");
+        assert!(path_mods.join("1.mod").is_file());
+        assert!(!staging_path(mods, 1).exists());
+    }
+
+    #[test]
+    fn interrupted_swaps_are_rolled_back() {
+        let dir = TempDir::new().unwrap();
+        let path_mods = dir.path().join("mod");
+        let mods = path_mods.to_str().unwrap();
+        install(dir.path(), mods, "first").unwrap();
+
+        // The old version was moved away, but the new one never arrived.
+        let staging = staging_path(mods, 1);
+        fs::create_dir_all(&staging).unwrap();
+        fs::rename(path_mods.join("1"), staging.join("old")).unwrap();
+        fs::rename(path_mods.join("1.mod"), staging.join("old.mod")).unwrap();
+
+        recover_backup(&staging, &path_mods.join("1"), &path_mods.join("1.mod")).unwrap();
+        assert_eq!(installed(&path_mods), "first");
+        assert!(path_mods.join("1.mod").is_file());
+    }
+
+    #[test]
+    fn finished_swaps_are_not_rolled_back() {
+        let dir = TempDir::new().unwrap();
+        let path_mods = dir.path().join("mod");
+        let mods = path_mods.to_str().unwrap();
+        install(dir.path(), mods, "first").unwrap();
+
+        // The new version is in place, but the old one was not deleted yet.
+        let staging = staging_path(mods, 1);
+        fs::create_dir_all(staging.join("old").join("common")).unwrap();
+        fs::write(staging.join("old").join("common").join("file.txt"), "old").unwrap();
+        fs::write(staging.join("old.mod"), "name=\"Mod\"").unwrap();
+        File::create(staging.join("swapped")).unwrap();
+
+        recover_backup(&staging, &path_mods.join("1"), &path_mods.join("1.mod")).unwrap();
+        assert_eq!(installed(&path_mods), "first");
+        assert!(!staging.join("old").exists());
+        assert!(!staging.join("old.mod").exists());
+        assert!(!staging.join("swapped").exists());
+    }
+
+    #[test]
+    fn only_the_newest_archived_versions_are_kept() {
+        let dir = TempDir::new().unwrap();
+        let cache_dir = dir.path().join("cache");
+        let path_mods = dir.path().join("mod");
+        let mods = path_mods.to_str().unwrap();
+        install(dir.path(), mods, "first").unwrap();
+
+        for &time_updated in &[10, 30, 20] {
+            archive_version(&cache_dir, mods, 1, time_updated, 2).unwrap();
+        }
+        assert_eq!(
+            archived_versions(&cache_dir, mods, 1).unwrap(),
+            vec![30, 20]
+        );
+        assert!(!version_archive(&cache_dir, mods, 1, 10).exists());
+
+        archive_version(&cache_dir, mods, 1, 40, 0).unwrap();
+        assert!(archived_versions(&cache_dir, mods, 1).unwrap().is_empty());
+    }
+}
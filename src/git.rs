@@ -0,0 +1,146 @@
+//! Cloning and updating git repositories with the `git` command line program.
+
+use std::{ffi::OsStr, path::Path};
+
+use anyhow::{anyhow, bail, Result};
+use tokio::process::Command;
+
+/// Runs git with `args` in `directory` and returns its trimmed output.
+async fn git<I, S>(directory: Option<&Path>, args: I) -> Result<String>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+{
+    let mut command = Command::new("git");
+    if let Some(directory) = directory {
+        let _command = command.arg("-C").arg(directory);
+    }
+    let output = command
+        .args(args)
+        .kill_on_drop(true)
+        .output()
+        .await
+        .map_err(|error| anyhow!("Could not run git: {}", error))?;
+
+    if !output.status.success() {
+        bail!(
+            "git failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Fails if `reference` would be read as an option by git.
+fn check_reference(reference: &str) -> Result<()> {
+    if reference.starts_with('-') {
+        bail!("{} is not a valid branch or tag!", reference);
+    }
+    Ok(())
+}
+
+/// Clones `url` into `target`, checking out the branch or tag `reference` if given.
+pub async fn clone(url: &str, reference: Option<&str>, target: &Path) -> Result<()> {
+    let mut args = vec!["clone", "--quiet"];
+    if let Some(reference) = reference {
+        check_reference(reference)?;
+        args.extend(["--branch", reference]);
+    }
+    args.extend(["--", url]);
+    git(
+        None,
+        args.into_iter().map(OsStr::new).chain([target.as_os_str()]),
+    )
+    .await?;
+    Ok(())
+}
+
+/// Fetches the repository at `repository` and fast-forwards it.
+///
+/// Branches are fast-forwarded to their remote state, tags are checked out again in case they were moved.
+pub async fn update(repository: &Path, reference: Option<&str>) -> Result<()> {
+    if let Some(reference) = reference {
+        check_reference(reference)?;
+    }
+    git(
+        Some(repository),
+        ["fetch", "--quiet", "--tags", "--force", "origin"],
+    )
+    .await?;
+
+    match reference {
+        Some(reference)
+            if git(
+                Some(repository),
+                [
+                    "rev-parse",
+                    "--verify",
+                    "--quiet",
+                    &format!("refs/tags/{}", reference),
+                ],
+            )
+            .await
+            .is_ok() =>
+        {
+            git(
+                Some(repository),
+                ["checkout", "--quiet", "--detach", reference],
+            )
+            .await?;
+        }
+        Some(reference) => {
+            git(
+                Some(repository),
+                [
+                    "merge",
+                    "--quiet",
+                    "--ff-only",
+                    &format!("origin/{}", reference),
+                ],
+            )
+            .await?;
+        }
+        None => {
+            git(
+                Some(repository),
+                ["merge", "--quiet", "--ff-only", "@{upstream}"],
+            )
+            .await?;
+        }
+    }
+    Ok(())
+}
+
+/// Returns the hash of the checked out commit.
+pub async fn head(repository: &Path) -> Result<String> {
+    git(Some(repository), ["rev-parse", "HEAD"]).await
+}
+
+/// Returns the commit time of the checked out commit in unix time.
+pub async fn head_time(repository: &Path) -> Result<u64> {
+    Ok(git(Some(repository), ["log", "-1", "--format=%ct"])
+        .await?
+        .parse()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+
+    use super::clone;
+
+    #[tokio::test]
+    async fn options_are_not_passed_as_url_or_reference() {
+        let dir = TempDir::new().unwrap();
+        let marker = dir.path().join("marker");
+        let upload_pack = format!("--upload-pack=touch {}", marker.display());
+
+        assert!(clone(&upload_pack, None, &dir.path().join("a"))
+            .await
+            .is_err());
+        assert!(clone("origin", Some(&upload_pack), &dir.path().join("b"))
+            .await
+            .is_err());
+        assert!(!marker.exists());
+    }
+}
@@ -0,0 +1,40 @@
+//! Interface functionality concering files provided by more than one mod.
+
+use anyhow::Result;
+
+use super::{Interface, Prompt, Transition};
+use crate::cli::{print_conflict_summary, print_file_conflict};
+
+impl<P: Prompt> Interface<P> {
+    /// Shows which enabled mods of the game at `game` replace files of each other.
+    pub fn conflicts(&mut self, game: usize) -> Result<Transition> {
+        let conflicts = self.manager.conflicts(game)?;
+        print_conflict_summary(&conflicts);
+        if conflicts.pairs.is_empty() {
+            return Ok(Transition::Back);
+        }
+
+        let lines: Vec<String> = conflicts
+            .pairs
+            .iter()
+            .map(|pair| format!("Show the files of {} and {}.", pair.winner, pair.loser))
+            .collect();
+        let mut items: Vec<&str> = lines.iter().map(String::as_str).collect();
+        items.push("Show all conflicting files.");
+        items.push("Go back.");
+
+        let files: Vec<_> = match self.prompt.select(&items)? {
+            Some(index) if index < conflicts.pairs.len() => conflicts
+                .files
+                .iter()
+                .filter(|conflict| conflicts.pairs[index].files.contains(&conflict.path))
+                .collect(),
+            Some(index) if index == conflicts.pairs.len() => conflicts.files.iter().collect(),
+            _ => return Ok(Transition::Back),
+        };
+        for conflict in files {
+            print_file_conflict(conflict);
+        }
+        Ok(Transition::Stay)
+    }
+}
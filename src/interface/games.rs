@@ -1,208 +1,215 @@
-//! Interface and filesystem functionality concering `Game`s.
+//! Interface functionality concering `Game`s.
 
-use crate::Game;
 use anyhow::Result;
-use async_recursion::async_recursion;
-use dialoguer::{theme::ColorfulTheme, Input, MultiSelect};
-use std::{collections::HashMap, path::Path};
+use pdx_mod_manager::{discovery::KNOWN_GAMES, Game};
+use std::path::Path;
 
-use super::{ui_selection, Interface};
+use super::{Interface, Prompt, Screen, Transition};
 
-impl Interface {
+/// Accepts names up to 30 characters long.
+pub(super) fn validate_title(input: &str) -> Result<(), &'static str> {
+    if input.len() <= 30 {
+        Ok(())
+    } else {
+        Err("Name is too long!")
+    }
+}
+
+/// Accepts paths that exist.
+pub(super) fn validate_path(input: &str) -> Result<(), &'static str> {
+    if Path::new(input).exists() {
+        Ok(())
+    } else {
+        Err("This path does not exist!")
+    }
+}
+
+impl<P: Prompt> Interface<P> {
     /// Shows all games saved in configuration.
-    #[async_recursion]
-    pub async fn show_games(&mut self) -> Result<()> {
-        if self.config.games.is_empty() {
+    pub fn show_games(&mut self) -> Result<Transition> {
+        if self.manager.games().is_empty() {
             println!("No game found, please add one!");
-            self.show_main_menu().await?;
+            return Ok(Transition::Back);
         }
 
-        let mut items_games = Vec::with_capacity(self.config.games.len() + 1);
+        let mut items_games = Vec::with_capacity(self.manager.games().len() + 1);
 
         items_games.extend(
-            self.config
-                .games
+            self.manager
+                .games()
                 .iter()
                 .map(|Game { title, .. }| title.as_str()),
         );
         items_games.push("Go back.");
 
-        let selection_games = ui_selection(&items_games);
-
-        if let Some(selection) = selection_games {
-            if selection == items_games.len() - 1 {
-                self.show_main_menu().await?;
-            } else {
-                self.selection = Some(selection);
-                self.show_game_options().await?;
+        Ok(match self.prompt.select(&items_games)? {
+            Some(selection) if selection < items_games.len() - 1 => {
+                Transition::Push(Screen::GameOptions(selection))
             }
-        }
-        Ok(())
+            _ => Transition::Back,
+        })
     }
 
     /// Shows options for `Game`s.
-    #[async_recursion]
-    pub async fn show_game_options(&mut self) -> Result<()> {
+    pub fn show_game_options(&mut self, game: usize) -> Result<Transition> {
         let game_details_items = [
             "Add mod.",
+            "Add collection.",
+            "Add local mod.",
+            "Add mod from git repository.",
+            "Add mod from web address.",
             "Delete mods.",
+            "Enable or disable mods.",
+            "Playsets.",
+            "Change load order.",
+            "Show file conflicts.",
             "Update mods.",
+            "Roll back or pin mods.",
             "Change game path.",
             "Change game name.",
             "Go back.",
         ];
 
-        let selection_game_options = ui_selection(&game_details_items);
-
-        if let Some(index) = selection_game_options {
-            match index {
-                0 => self.add_mod().await,
-                1 => self.delete_mods().await,
-                2 => self.update_all_game_mods().await,
-                3 => self.change_game_path().await,
-                4 => self.change_game_name().await,
-                5 => {
-                    self.selection = None;
-                    self.show_games().await
-                }
-                _ => Ok(()),
-            }
-        } else {
-            println!("User did not select anything");
-            Ok(())
-        }
+        Ok(match self.prompt.select(&game_details_items)? {
+            Some(0) => Transition::Push(Screen::AddMod(game)),
+            Some(1) => Transition::Push(Screen::AddCollection(game)),
+            Some(2) => Transition::Push(Screen::AddLocalMod(game)),
+            Some(3) => Transition::Push(Screen::AddGitMod(game)),
+            Some(4) => Transition::Push(Screen::AddUrlMod(game)),
+            Some(5) => Transition::Push(Screen::DeleteMods(game)),
+            Some(6) => Transition::Push(Screen::EnableMods(game)),
+            Some(7) => Transition::Push(Screen::Playsets(game)),
+            Some(8) => Transition::Push(Screen::LoadOrder(game)),
+            Some(9) => Transition::Push(Screen::Conflicts(game)),
+            Some(10) => Transition::Push(Screen::UpdateGameMods(game)),
+            Some(11) => Transition::Push(Screen::ModVersions(game)),
+            Some(12) => Transition::Push(Screen::ChangeGamePath(game)),
+            Some(13) => Transition::Push(Screen::ChangeGameName(game)),
+            _ => Transition::Back,
+        })
     }
 
     /// Adds `Game`s.
-    pub async fn add_games_manually(&mut self) -> Result<()> {
-        let title: String = Input::with_theme(&ColorfulTheme::default())
-            .with_prompt("Type in the name of the game. Name can be up to 30 characters long!")
-            .validate_with(|input: &String| -> Result<(), &str> {
-                if input.len() <= 30 {
-                    Ok(())
-                } else {
-                    Err("Name is too long!")
-                }
-            })
-            .interact_text()?;
-
-        let path_mods: String = Input::with_theme(&ColorfulTheme::default())
-            .with_prompt("Type or copy paste in the path to the mod folder.\nPlease make sure to put in the correct path! ex: C:\\Users\\Alice\\Documents\\Paradox Interactive\\Stellaris\\mod")
-            .validate_with(|input: &String| -> Result<(), &str> {
-                if Path::new(input).exists() {
-                    Ok(())
-                } else {
-                    Err("This path does not exist!")
-                }
+    pub fn add_games_manually(&mut self) -> Result<Transition> {
+        let title = self.prompt.input(
+            "Type in the name of the game. Name can be up to 30 characters long!",
+            validate_title,
+        )?;
+
+        let path_mods = self.prompt.input(
+            "Type or copy paste in the path to the mod folder.\nPlease make sure to put in the correct path! ex: C:\\Users\\Alice\\Documents\\Paradox Interactive\\Stellaris\\mod",
+            validate_path,
+        )?;
+
+        let mut items: Vec<&str> = KNOWN_GAMES.iter().map(|game| game.title).collect();
+        items.push("Another game.");
+        println!("Which game is this? It is used to check that mods belong to the game.");
+        let app_id = self
+            .prompt
+            .select(&items)?
+            .and_then(|index| KNOWN_GAMES.get(index))
+            .map(|game| game.app_id);
+
+        self.manager.add_game(title, path_mods, app_id)?;
+        println!("Game added successfully!");
+        Ok(Transition::Back)
+    }
+
+    /// Adds games found on this computer.
+    pub fn detect_games(&mut self) -> Result<Transition> {
+        let found = self.manager.discover_games();
+        if found.is_empty() {
+            println!("No new games found, please add them manually!");
+            return Ok(Transition::Back);
+        }
+
+        let items: Vec<String> = found
+            .iter()
+            .map(|game| {
+                format!(
+                    "{} ({}): {}",
+                    game.game.title,
+                    game.location,
+                    game.path_mods.display()
+                )
             })
-            .interact_text()
-            ?;
+            .collect();
 
-        self.config.games.push(Game {
-            title,
-            path_mods,
-            mods: HashMap::new(),
-        });
+        let chosen = self.prompt.multi_select(
+            "Select the games to add with space, confirm with enter.",
+            &items,
+        )?;
 
-        self.config.update_config_file()?;
-        println!("Game added successfully!");
-        self.show_main_menu().await
+        for index in chosen {
+            let title = self
+                .manager
+                .add_discovered_game(found.get(index).expect("could not find game"))?;
+            println!("Added {}!", title);
+        }
+        Ok(Transition::Back)
     }
 
     /// Update all mods for a selected game.
-    pub async fn update_all_game_mods(&mut self) -> Result<()> {
-        for (_, item_mod) in self
-            .config
-            .games
-            .get(self.selection.expect("selection empty"))
-            .expect("failed to get game")
-            .mods
-            .clone()
-        {
-            self.update_mod(item_mod.id, self.selection.expect("selection empty"))
-                .await?;
-        }
-        self.show_game_options().await
+    pub async fn update_all_game_mods(&mut self, game: usize) -> Result<Transition> {
+        let downloads = self.manager.downloads();
+        crate::cancel_downloads_on_input(downloads, self.manager.update_game_mods(game)).await?;
+        Ok(Transition::Back)
     }
 
     /// Deletes a selectet game.
-    pub async fn delete_game(&mut self) -> Result<()> {
-        let games = &mut self.config.games;
+    pub fn delete_game(&mut self) -> Result<Transition> {
         let mut items = vec![];
-        for game in games.clone() {
-            items.push(game.title);
+        for game in self.manager.games() {
+            items.push(game.title.clone());
         }
 
         if items.is_empty() {
             println!("You have no games configured yet!");
-            return self.show_main_menu().await;
+            return Ok(Transition::Back);
         }
 
-        let chosen: Vec<usize> = MultiSelect::with_theme(&ColorfulTheme::default())
-            .with_prompt("Select with space, confirm with enter. Confirming without selecting anything will cancel.")
-            .items(&items)
-            .interact()?;
+        let chosen = self.prompt.multi_select(
+            "Select with space, confirm with enter. Confirming without selecting anything will cancel.",
+            &items,
+        )?;
 
         if chosen.is_empty() {
-            return self.show_main_menu().await;
+            return Ok(Transition::Back);
         }
 
-        for index in chosen {
-            let _old = games.remove(index);
+        for index in chosen.into_iter().rev() {
+            self.manager.remove_game(index)?;
         }
 
-        self.config.update_config_file()?;
-
         println!("Games removed!");
-        self.show_main_menu().await
+        Ok(Transition::Back)
     }
 
     /// Change game of a selected game.
-    pub async fn change_game_name(&mut self) -> Result<()> {
-        let game = self
-            .config
-            .games
-            .get_mut(self.selection.expect("game selection empty"))
-            .expect("get game failed");
-        let old_title = game.title.clone();
-        let new_title: String = Input::with_theme(&ColorfulTheme::default())
-            .with_prompt("Type in the new name of the game. Name can be up to 30 characters long!")
-            .validate_with(|input: &String| -> Result<(), &str> {
-                if input.len() <= 30 {
-                    Ok(())
-                } else {
-                    Err("Name is too long!")
-                }
-            })
-            .interact_text()?;
-
-        game.title = new_title.clone();
+    pub fn change_game_name(&mut self, game: usize) -> Result<Transition> {
+        let old_title = self.manager.game(game)?.title.clone();
+        let new_title = self.prompt.input(
+            "Type in the new name of the game. Name can be up to 30 characters long!",
+            validate_title,
+        )?;
+
+        self.manager.rename_game(game, new_title.clone())?;
         println!(
             "Name of {} changed to {} successfully!",
             old_title, new_title
         );
-        self.show_games().await
+        Ok(Transition::Back)
     }
 
     /// Change mod path of the selected game.
-    pub async fn change_game_path(&mut self) -> Result<()> {
-        let new_path: String = Input::with_theme(&ColorfulTheme::default())
-            .with_prompt("Type or copy paste in the new path to the mod folder.\nPlease make sure to put in the correct path! ex: C:\\Users\\Alice\\Documents\\Paradox Interactive\\Stellaris\\mod")
-            .validate_with(|input: &String| -> Result<(), &str> {
-                if input.len() <= 30 {
-                    Ok(())
-                } else {
-                    Err("Name is too long!")
-                }
-            })
-            .interact_text()?;
+    pub fn change_game_path(&mut self, game: usize) -> Result<Transition> {
+        let new_path = self.prompt.input(
+            "Type or copy paste in the new path to the mod folder.\nPlease make sure to put in the correct path! ex: C:\\Users\\Alice\\Documents\\Paradox Interactive\\Stellaris\\mod",
+            validate_path,
+        )?;
 
-        self.config
-            .games
-            .get_mut(self.selection.expect("no game selection"))
-            .expect("get game failed")
-            .path_mods = new_path.clone();
+        self.manager.set_game_path(game, new_path)?;
         println!("Changed path successfully!");
-        self.show_games().await
+        Ok(Transition::Back)
     }
 }
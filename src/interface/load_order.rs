@@ -0,0 +1,119 @@
+//! Interface functionality concering the load order of mods.
+
+use anyhow::Result;
+use pdx_mod_manager::enabled::mod_entry;
+
+use super::{Interface, Prompt, Transition};
+
+/// Accepts positions in a list, which start at 1.
+fn validate_position(input: &str) -> Result<(), &'static str> {
+    match input.trim().parse::<usize>() {
+        Ok(position) if position > 0 => Ok(()),
+        _ => Err("Please type in a position, starting at 1!"),
+    }
+}
+
+impl<P: Prompt> Interface<P> {
+    /// Moves and sorts the enabled mods of the game at `game`.
+    pub fn load_order(&mut self, game: usize) -> Result<Transition> {
+        let order = self.manager.load_order(game)?;
+        if order.is_empty() {
+            println!("You have no mods enabled for that game!");
+            return Ok(Transition::Back);
+        }
+
+        let lines: Vec<String> = order
+            .iter()
+            .enumerate()
+            .map(|(position, (_, title))| format!("{}. {}", position + 1, title))
+            .collect();
+        let mut items: Vec<&str> = lines.iter().map(String::as_str).collect();
+        items.push("Sort automatically.");
+        items.push("Go back.");
+
+        let from = match self.prompt.select(&items)? {
+            Some(index) if index < order.len() => index,
+            Some(index) if index == order.len() => {
+                for cycle in self.manager.sort_load_order(game)? {
+                    println!(
+                        "Warning: load order cycle {} -> {}",
+                        cycle.join(" -> "),
+                        cycle[0]
+                    );
+                }
+                println!("Load order sorted!");
+                return Ok(Transition::Back);
+            }
+            _ => return Ok(Transition::Back),
+        };
+
+        match self.prompt.select(&[
+            "Move up.",
+            "Move down.",
+            "Move to position.",
+            "Always load after another mod.",
+            "Go back.",
+        ])? {
+            Some(0) => self
+                .manager
+                .move_in_load_order(game, from, from.saturating_sub(1))?,
+            Some(1) => self.manager.move_in_load_order(game, from, from + 1)?,
+            Some(2) => {
+                let to = self
+                    .prompt
+                    .input("Type in the new position.", validate_position)?;
+                let to = to.trim().parse::<usize>()?;
+                self.manager.move_in_load_order(game, from, to - 1)?;
+            }
+            Some(3) => self.add_load_rule(game, &order, from)?,
+            _ => {}
+        }
+        Ok(Transition::Stay)
+    }
+
+    /// Lets the user pick a mod the mod at `from` of `order` always loads after.
+    fn add_load_rule(
+        &mut self,
+        game: usize,
+        order: &[(String, String)],
+        from: usize,
+    ) -> Result<()> {
+        let game_info = self.manager.game(game)?;
+        let key_of = |entry: &str| {
+            game_info
+                .mods
+                .keys()
+                .find(|key| mod_entry(&game_info.path_mods, key) == entry)
+                .cloned()
+        };
+        let key = match key_of(&order[from].0) {
+            Some(key) => key,
+            None => {
+                println!("{} is not managed by this program!", order[from].1);
+                return Ok(());
+            }
+        };
+        let others: Vec<(String, String)> = order
+            .iter()
+            .enumerate()
+            .filter(|(position, _)| *position != from)
+            .filter_map(|(_, (entry, title))| key_of(entry).map(|key| (key, title.clone())))
+            .collect();
+
+        let mut items: Vec<&str> = others.iter().map(|(_, title)| title.as_str()).collect();
+        items.push("Go back.");
+        println!("Which mod should {} load after?", order[from].1);
+        if let Some((after, title)) = self
+            .prompt
+            .select(&items)?
+            .and_then(|index| others.get(index))
+        {
+            self.manager.add_load_rule(game, &key, after.parse()?)?;
+            println!(
+                "{} loads after {} when the load order is sorted!",
+                order[from].1, title
+            );
+        }
+        Ok(())
+    }
+}
@@ -1,90 +1,322 @@
 //! Interface functionality.
+//!
+//! The interface is a stack of [`Screen`]s. Each screen prompts the user once and returns a [`Transition`],
+//! which the event loop in [`Interface::run`] applies to the stack.
 
+mod conflicts;
 mod games;
+mod load_order;
 mod mods;
+mod playsets;
+mod prompt;
 
 use anyhow::Result;
-use async_recursion::async_recursion;
-use std::{
-    fs::{self, File},
-    io::Write,
-};
-
-use crate::{remote::Remote, Config};
-use dialoguer::{console::Term, theme::ColorfulTheme, Select};
-
-/// Uses the dialoguer crate to give the user a selection.
-fn ui_selection(items: &[&str]) -> Option<usize> {
-    Select::with_theme(&ColorfulTheme::default())
-        .items(items)
-        .default(0)
-        .interact_on_opt(&Term::stderr())
-        .expect("dialouger error")
+use pdx_mod_manager::ModManager;
+
+pub use prompt::{Dialoguer, Prompt};
+
+/// Screens of the interface.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Screen {
+    /// The main menu.
+    MainMenu,
+    /// Selection of a configured game.
+    Games,
+    /// Options for the game at the given position.
+    GameOptions(usize),
+    /// Adds a game manually.
+    AddGame,
+    /// Adds detected games.
+    DetectGames,
+    /// Removes games from the configuration.
+    DeleteGames,
+    /// Updates the mods of all games.
+    UpdateAllMods,
+    /// Adds a mod to the game at the given position.
+    AddMod(usize),
+    /// Adds a workshop collection to the game at the given position.
+    AddCollection(usize),
+    /// Adds a mod from this computer to the game at the given position.
+    AddLocalMod(usize),
+    /// Adds a mod from a git repository to the game at the given position.
+    AddGitMod(usize),
+    /// Adds a mod from a web address to the game at the given position.
+    AddUrlMod(usize),
+    /// Removes mods from the game at the given position.
+    DeleteMods(usize),
+    /// Enables and disables mods of the game at the given position.
+    EnableMods(usize),
+    /// Manages the playsets of the game at the given position.
+    Playsets(usize),
+    /// Orders the enabled mods of the game at the given position.
+    LoadOrder(usize),
+    /// Shows the files enabled mods of the game at the given position replace in each other.
+    Conflicts(usize),
+    /// Updates the mods of the game at the given position.
+    UpdateGameMods(usize),
+    /// Rolls back and pins mods of the game at the given position.
+    ModVersions(usize),
+    /// Changes the mod folder of the game at the given position.
+    ChangeGamePath(usize),
+    /// Changes the name of the game at the given position.
+    ChangeGameName(usize),
+}
+
+/// What the event loop does after a screen was shown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transition {
+    /// Shows another screen, going back returns to the current one.
+    Push(Screen),
+    /// Shows the current screen again.
+    Stay,
+    /// Returns to the previous screen.
+    Back,
+    /// Ends the session.
+    Exit,
 }
 
-/// Parent struct for the whole program.
-pub struct Interface {
-    /// Holds the saved configuration of the program.
-    pub config: Config,
-    /// The currently selected `Game`.
-    pub selection: Option<usize>,
-    /// Holds the current `reqwest::Client`.
-    pub remote: Remote,
+/// Interactive front-end for the `ModManager`.
+pub struct Interface<P: Prompt = Dialoguer> {
+    /// Holds the configuration and does the actual work.
+    pub manager: ModManager,
+    /// Where user input comes from.
+    pub prompt: P,
 }
 
 impl Interface {
-    /// Instanciates a new `Interface` struct.
-    pub fn new() -> Result<Self> {
-        let config = if let Ok(config) = fs::read_to_string("config.toml") {
-            config
-        } else {
-            let mut file = File::create("config.toml")?;
-            let new_config = String::from("games = []");
-            file.write_all(b"games = []")?;
-            new_config
-        };
-
-        Ok(Self {
-            config: toml::from_str::<Config>(&config)?,
-            selection: None,
-            remote: Remote::new(),
-        })
+    /// Instanciates a new `Interface` struct prompting on the terminal.
+    pub const fn new(manager: ModManager) -> Self {
+        Self::with_prompt(manager, Dialoguer)
+    }
+}
+
+impl<P: Prompt> Interface<P> {
+    /// Instanciates a new `Interface` struct reading input from `prompt`.
+    pub const fn with_prompt(manager: ModManager, prompt: P) -> Self {
+        Self { manager, prompt }
+    }
+
+    /// Runs the event loop until the user exits or leaves the main menu.
+    ///
+    /// Errors of a screen are shown to the user and lead back to the previous screen,
+    /// errors of the main menu end the session. Ctrl+C cancels the operation of the current screen.
+    pub async fn run(&mut self) -> Result<()> {
+        let mut stack = vec![Screen::MainMenu];
+
+        while let Some(&screen) = stack.last() {
+            self.manager.reset_cancellation();
+            let token = self.manager.cancellation();
+            let transition = match crate::cancel_on_ctrl_c(token, self.show(screen)).await {
+                Ok(transition) => transition,
+                Err(error) if stack.len() == 1 => return Err(error),
+                Err(error) => {
+                    println!("Error: {:#}", error);
+                    Transition::Back
+                }
+            };
+
+            match transition {
+                Transition::Push(next) => stack.push(next),
+                Transition::Stay => {}
+                Transition::Back => {
+                    let _old = stack.pop();
+                }
+                Transition::Exit => stack.clear(),
+            }
+        }
+        Ok(())
+    }
+
+    /// Shows a single screen and returns what should happen next.
+    pub async fn show(&mut self, screen: Screen) -> Result<Transition> {
+        match screen {
+            Screen::MainMenu => self.show_main_menu(),
+            Screen::Games => self.show_games(),
+            Screen::GameOptions(game) => self.show_game_options(game),
+            Screen::AddGame => self.add_games_manually(),
+            Screen::DetectGames => self.detect_games(),
+            Screen::DeleteGames => self.delete_game(),
+            Screen::UpdateAllMods => self.update_all_mods().await,
+            Screen::AddMod(game) => self.add_mod(game).await,
+            Screen::AddCollection(game) => self.add_collection(game).await,
+            Screen::AddLocalMod(game) => self.add_local_mod(game),
+            Screen::AddGitMod(game) => self.add_git_mod(game).await,
+            Screen::AddUrlMod(game) => self.add_url_mod(game).await,
+            Screen::DeleteMods(game) => self.delete_mods(game),
+            Screen::EnableMods(game) => self.enable_mods(game),
+            Screen::Playsets(game) => self.playsets(game),
+            Screen::LoadOrder(game) => self.load_order(game),
+            Screen::Conflicts(game) => self.conflicts(game),
+            Screen::UpdateGameMods(game) => self.update_all_game_mods(game).await,
+            Screen::ModVersions(game) => self.mod_versions(game),
+            Screen::ChangeGamePath(game) => self.change_game_path(game),
+            Screen::ChangeGameName(game) => self.change_game_name(game),
+        }
     }
 
     /// Shows the main menu.
-    #[async_recursion]
-    pub async fn show_main_menu(&mut self) -> Result<()> {
+    pub fn show_main_menu(&mut self) -> Result<Transition> {
         let items_options = [
             "Show games.",
+            "Detect installed games.",
             "Add new game manually.",
             "Update all mods.",
             "Delete game. This just deletes the configuration for this program, not the actual game.",
             "Exit.",
         ];
 
-        let selection_options = ui_selection(&items_options);
-
-        if let Some(index) = selection_options {
-            match index {
-                0 => self.show_games().await,
-                1 => self.add_games_manually().await,
-                2 => self.update_all_mods().await,
-                3 => self.delete_game().await,
-                _ => Ok(()),
+        Ok(match self.prompt.select(&items_options)? {
+            Some(0) => Transition::Push(Screen::Games),
+            Some(1) => Transition::Push(Screen::DetectGames),
+            Some(2) => Transition::Push(Screen::AddGame),
+            Some(3) => Transition::Push(Screen::UpdateAllMods),
+            Some(4) => Transition::Push(Screen::DeleteGames),
+            Some(_) => Transition::Exit,
+            None => {
+                println!("User did not select anything");
+                Transition::Exit
             }
-        } else {
-            println!("User did not select anything");
-            Ok(())
-        }
+        })
     }
 
     /// updates all mods.
-    pub async fn update_all_mods(&mut self) -> Result<()> {
-        for (i, game) in self.config.games.clone().into_iter().enumerate() {
-            for (_, item_mod) in game.mods.clone() {
-                self.update_mod(item_mod.id, i).await?;
-            }
+    pub async fn update_all_mods(&mut self) -> Result<Transition> {
+        let downloads = self.manager.downloads();
+        crate::cancel_downloads_on_input(downloads, self.manager.update_all_mods()).await?;
+        Ok(Transition::Back)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pdx_mod_manager::ModManager;
+    use tempfile::TempDir;
+
+    use super::{
+        prompt::{Answer, Script},
+        Interface, Screen, Transition,
+    };
+
+    /// Creates an interface for one game in a temporary folder, which answers with `answers`.
+    fn interface(answers: Vec<Answer>) -> (TempDir, Interface<Script>) {
+        let dir = TempDir::new().unwrap();
+        let manager = ModManager::for_test(dir.path());
+        (dir, Interface::with_prompt(manager, Script::new(answers)))
+    }
+
+    #[test]
+    fn main_menu_transitions() {
+        let (_dir, mut interface) = interface(vec![
+            Answer::Select(Some(0)),
+            Answer::Select(Some(2)),
+            Answer::Select(Some(5)),
+            Answer::Select(None),
+        ]);
+        for expected in [
+            Transition::Push(Screen::Games),
+            Transition::Push(Screen::AddGame),
+            Transition::Exit,
+            Transition::Exit,
+        ] {
+            assert_eq!(interface.show_main_menu().unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn game_options_transitions() {
+        let (_dir, mut interface) = interface(vec![
+            Answer::Select(Some(0)),
+            Answer::Select(Some(8)),
+            Answer::Select(Some(9)),
+            Answer::Select(Some(14)),
+            Answer::Select(None),
+        ]);
+        for expected in [
+            Transition::Push(Screen::AddMod(0)),
+            Transition::Push(Screen::LoadOrder(0)),
+            Transition::Push(Screen::Conflicts(0)),
+            Transition::Back,
+            Transition::Back,
+        ] {
+            assert_eq!(interface.show_game_options(0).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn games_screen_goes_back() {
+        let (_dir, mut interface) =
+            interface(vec![Answer::Select(Some(0)), Answer::Select(Some(1))]);
+        assert_eq!(
+            interface.show_games().unwrap(),
+            Transition::Push(Screen::GameOptions(0))
+        );
+        assert_eq!(interface.show_games().unwrap(), Transition::Back);
+    }
+
+    #[test]
+    fn delete_games_removes_the_selected_games() {
+        let (_dir, mut interface) = interface(vec![
+            Answer::MultiSelect(Vec::new()),
+            Answer::MultiSelect(vec![0]),
+        ]);
+        assert_eq!(interface.delete_game().unwrap(), Transition::Back);
+        assert_eq!(interface.manager.games().len(), 1);
+        assert_eq!(interface.delete_game().unwrap(), Transition::Back);
+        assert!(interface.manager.games().is_empty());
+    }
+
+    #[test]
+    fn playsets_are_only_deleted_when_confirmed() {
+        let (_dir, mut interface) = interface(vec![
+            // Create a playset, refuse to delete it, then delete it.
+            Answer::Select(Some(0)),
+            Answer::Input("Multiplayer".to_string()),
+            Answer::Select(Some(0)),
+            Answer::Select(Some(2)),
+            Answer::Confirm(false),
+            Answer::Select(Some(0)),
+            Answer::Select(Some(2)),
+            Answer::Confirm(true),
+        ]);
+        for playsets in [1, 1, 0] {
+            assert_eq!(interface.playsets(0).unwrap(), Transition::Back);
+            assert_eq!(interface.manager.game(0).unwrap().playsets.len(), playsets);
         }
-        self.show_main_menu().await
+    }
+
+    #[tokio::test]
+    async fn run_navigates_back_to_the_main_menu() {
+        let (_dir, mut interface) = interface(vec![
+            // Main menu, games, first game, go back, go back, exit.
+            Answer::Select(Some(0)),
+            Answer::Select(Some(0)),
+            Answer::Select(Some(14)),
+            Answer::Select(Some(1)),
+            Answer::Select(Some(5)),
+        ]);
+        interface.run().await.unwrap();
+        assert!(interface.prompt.answers.is_empty());
+    }
+
+    #[tokio::test]
+    async fn run_shows_errors_of_screens_and_goes_back() {
+        let (dir, mut interface) = interface(Vec::new());
+        // Main menu, add the game a second time, which fails, exit.
+        interface.prompt.answers.extend(vec![
+            Answer::Select(Some(2)),
+            Answer::Input("Stellaris".to_string()),
+            Answer::Input(dir.path().to_string_lossy().into_owned()),
+            Answer::Select(None),
+            Answer::Select(Some(5)),
+        ]);
+        interface.run().await.unwrap();
+        assert!(interface.prompt.answers.is_empty());
+        assert_eq!(interface.manager.games().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn run_returns_errors_of_the_main_menu() {
+        let (_dir, mut interface) = interface(Vec::new());
+        assert!(interface.run().await.is_err());
     }
 }
@@ -1,167 +1,334 @@
-//! Interface and filesystem functionality concering `Mod`s.
+//! Interface functionality concering `Mod`s.
 
 use anyhow::Result;
-use async_recursion::async_recursion;
-use dialoguer::{theme::ColorfulTheme, Confirm, Input, MultiSelect};
-use std::{fs, io::Cursor};
 
-use crate::{filesystem::write_mod, Mod};
+use super::{games::validate_path, Interface, Prompt, Transition};
+use crate::cli::{format_time, print_plan};
 
-use super::Interface;
+/// Accepts any text.
+const fn accept_any(_input: &str) -> Result<(), &'static str> {
+    Ok(())
+}
+
+/// Accepts http and https addresses.
+fn validate_url(input: &str) -> Result<(), &'static str> {
+    let input = input.trim();
+    if input.starts_with("http://") || input.starts_with("https://") {
+        Ok(())
+    } else {
+        Err("Please type in an address starting with http:// or https://!")
+    }
+}
+
+/// Accepts SHA-256 hashes.
+fn validate_sha256(input: &str) -> Result<(), &'static str> {
+    let input = input.trim();
+    if input.len() == 64 && input.chars().all(|c| c.is_ascii_hexdigit()) {
+        Ok(())
+    } else {
+        Err("A SHA-256 hash has 64 hexadecimal digits!")
+    }
+}
 
-impl Interface {
+/// Accepts workshop ids.
+fn validate_id(input: &str) -> Result<(), &'static str> {
+    if input.trim().parse::<u64>().is_ok() {
+        Ok(())
+    } else {
+        Err("Please only type in numbers!")
+    }
+}
+
+impl<P: Prompt> Interface<P> {
     /// Function to add mods.
-    #[async_recursion]
-    pub async fn add_mod(&mut self) -> Result<()> {
-        let game = self
-            .config
-            .games
-            .get_mut(self.selection.expect("game selection is none"))
-            .expect("get game failed");
-        let item_id = Input::with_theme(&ColorfulTheme::default())
-            .with_prompt("Type in the id of the mod!")
-            .validate_with(|input: &String| -> Result<(), &str> {
-                if input.find(char::is_numeric).is_some() {
-                    Ok(())
-                } else {
-                    println!("{}", input);
-                    Err("Please only type in numbers!")
+    pub async fn add_mod(&mut self, game: usize) -> Result<Transition> {
+        let game_title = self.manager.game(game)?.title.clone();
+        let item_id = self
+            .prompt
+            .input("Type in the id of the mod!", validate_id)?
+            .trim()
+            .parse::<u64>()?;
+
+        let plan = self.manager.plan_install(item_id).await?;
+        for (_, item_info) in &plan.items {
+            if let Err(error) = self.manager.check_item(game, item_info) {
+                println!("Warning: {}", error);
+                if !self.prompt.confirm("Do you want to install it anyway?")? {
+                    return Ok(Transition::Back);
                 }
-            })
-            .interact_text()
-            .expect("dialoguer error")
-            .parse::<u64>()
-            .expect("could not parse string to u64");
-
-        let (item_title, item_time_updated) = self.remote.get_item_info(item_id).await?;
-        let proceed = Confirm::with_theme(&ColorfulTheme::default())
-            .with_prompt(format!(
-                "Do you want to download and install {} for {}?",
-                item_title, game.title
-            ))
-            .interact()?;
-
-        if !proceed {
-            return self.show_game_options().await;
+            }
         }
+        print_plan(&plan);
 
-        let file = self.remote.download_item(item_id).await?;
-        println!("Download finished!");
-        println!("### Installing ###");
-        let zip = zip::ZipArchive::new(Cursor::new(file))?;
-        write_mod(item_id, item_title.clone(), zip, &game.path_mods)?;
+        let proceed = self.prompt.confirm(&format!(
+            "Do you want to download and install {} for {}?",
+            plan.title(item_id).unwrap_or("the mod"),
+            game_title
+        ))?;
 
-        println!("Updating config file.");
-        if let Some(item) = game.mods.get_mut(&item_id.to_string()) {
-            item.time_updated = item_time_updated;
-        } else {
-            let _old = game.mods.insert(
-                item_id.to_string(),
-                Mod {
-                    id: item_id,
-                    title: item_title,
-                    time_updated: item_time_updated,
-                },
-            );
+        if proceed {
+            self.manager.install_plan(game, &plan).await?;
         }
 
-        self.config.update_config_file()?;
-        println!("Mod installed!.");
+        Ok(Transition::Back)
+    }
+
+    /// Lets the user pick members of a workshop collection and installs them.
+    pub async fn add_collection(&mut self, game: usize) -> Result<Transition> {
+        let collection_id = self
+            .prompt
+            .input("Type in the id of the collection!", validate_id)?
+            .trim()
+            .parse::<u64>()?;
+
+        let (collection_info, members) = self.manager.get_collection(collection_id).await?;
+        if let Err(error) = self.manager.check_item(game, &collection_info) {
+            println!("Warning: {}", error);
+            if !self.prompt.confirm("Do you want to install it anyway?")? {
+                return Ok(Transition::Back);
+            }
+        }
+
+        let items: Vec<(String, bool)> = members
+            .iter()
+            .map(|(item_id, info)| (format!("{} {}", item_id, info.title), true))
+            .collect();
+        let chosen = self.prompt.multi_select_checked(
+            &format!(
+                "Mods in {}. Select with space, confirm with enter.",
+                collection_info.title
+            ),
+            &items,
+        )?;
+        if chosen.is_empty() {
+            return Ok(Transition::Back);
+        }
+
+        let selected: Vec<u64> = chosen
+            .into_iter()
+            .filter_map(|index| members.get(index).map(|(item_id, _)| *item_id))
+            .collect();
+        self.manager
+            .install_collection(
+                game,
+                collection_id,
+                collection_info.title,
+                members.into_iter().map(|(item_id, _)| item_id).collect(),
+                &selected,
+            )
+            .await?;
+
+        println!("Collection installed!");
+        Ok(Transition::Back)
+    }
+
+    /// Installs a mod from a `.zip` archive or folder on this computer.
+    pub fn add_local_mod(&mut self, game: usize) -> Result<Transition> {
+        let path = self.prompt.input(
+            "Type or copy paste in the path to the .zip archive or mod folder.",
+            validate_path,
+        )?;
+        let title = self.manager.install_local(game, &path, None)?;
+        println!("{} installed!", title);
+        Ok(Transition::Back)
+    }
+
+    /// Clones a git repository and installs the mod in it.
+    pub async fn add_git_mod(&mut self, game: usize) -> Result<Transition> {
+        let url = self.prompt.input(
+            "Type or copy paste in the address of the repository.",
+            accept_any,
+        )?;
+        let reference = if self
+            .prompt
+            .confirm("Do you want to follow a specific branch or tag?")?
+        {
+            Some(
+                self.prompt
+                    .input("Type in the branch or tag.", accept_any)?,
+            )
+        } else {
+            None
+        };
+        let subdir = if self
+            .prompt
+            .confirm("Is the mod in a folder inside the repository?")?
+        {
+            Some(
+                self.prompt
+                    .input("Type in the folder inside the repository.", accept_any)?,
+            )
+        } else {
+            None
+        };
+
+        let title = self
+            .manager
+            .install_git(game, &url, reference, subdir, None)
+            .await?;
+        println!("{} installed!", title);
+        Ok(Transition::Back)
+    }
+
+    /// Downloads and installs a `.zip` archive from a web address.
+    pub async fn add_url_mod(&mut self, game: usize) -> Result<Transition> {
+        let url = self.prompt.input(
+            "Type or copy paste in the address of the archive.",
+            validate_url,
+        )?;
+        let sha256 = if self
+            .prompt
+            .confirm("Do you want to check the archive against a SHA-256 hash?")?
+        {
+            Some(
+                self.prompt
+                    .input("Type or copy paste in the hash.", validate_sha256)?,
+            )
+        } else {
+            None
+        };
 
-        self.show_game_options().await
+        let title = self.manager.install_url(game, &url, sha256, None).await?;
+        println!("{} installed!", title);
+        Ok(Transition::Back)
     }
 
     /// Deletes mods.
-    #[async_recursion]
-    pub async fn delete_mods(&mut self) -> Result<()> {
-        let game = self
-            .config
-            .games
-            .get_mut(self.selection.expect("game selection is none"))
-            .expect("get game failed");
-
-        let mods = &mut game.mods;
+    pub fn delete_mods(&mut self, game: usize) -> Result<Transition> {
         let mut items = vec![];
         let mut keys = vec![];
-        for (key, modif) in mods.clone() {
+        for (key, modif) in &self.manager.game(game)?.mods {
             items.push(modif.title.clone());
-            keys.push(key);
+            keys.push(key.clone());
         }
 
         if items.is_empty() {
             println!("You have no mods installed for that game!");
-            return self.show_game_options().await;
+            return Ok(Transition::Back);
         }
 
-        let chosen: Vec<usize> = MultiSelect::with_theme(&ColorfulTheme::default())
-            .with_prompt("Select with space, confirm with enter. Confirming without selecting anything will cancel.")
-            .items(&items)
-            .interact()?;
-
-        if chosen.is_empty() {
-            return self.show_game_options().await;
-        }
+        let chosen = self.prompt.multi_select(
+            "Select with space, confirm with enter. Confirming without selecting anything will cancel.",
+            &items,
+        )?;
 
         for index in chosen {
-            fs::remove_dir_all(format!(
-                "{}/{}",
-                game.path_mods,
-                keys.get(index).expect("could not find key")
-            ))?;
-            fs::remove_file(format!(
-                "{}/{}{}",
-                game.path_mods,
-                keys.get(index).expect("could not find key"),
-                ".mod"
-            ))?;
-            let _old = mods
-                .remove(keys.get(index).expect("could not find key"))
-                .expect("could not remove mod from config");
+            self.manager
+                .remove_mod(game, keys.get(index).expect("could not find key"))?;
         }
 
-        self.config.update_config_file()?;
-
         println!("Mods removed!");
-        self.show_game_options().await
+        Ok(Transition::Back)
     }
 
-    /// updates mod
-    pub async fn update_mod(&mut self, item_id: u64, game_selection: usize) -> Result<()> {
-        let game = self
-            .config
-            .games
-            .get_mut(game_selection)
-            .expect("get game failed");
-
-        let modif = game
-            .mods
-            .get_mut(&item_id.to_string())
-            .expect("get mod failed");
-
-        let (item_title, item_time_updated) = self.remote.get_item_info(item_id).await?;
-
-        if modif.time_updated >= item_time_updated {
-            println!(
-                "Mod {} for {} is already up to date!",
-                modif.title, game.title
-            );
-            return Ok(());
+    /// Enables and disables mods of the game at `game` in its list of enabled mods.
+    pub fn enable_mods(&mut self, game: usize) -> Result<Transition> {
+        let _entries = self.manager.read_enabled_mods(game)?;
+        let mut mods: Vec<_> = self.manager.game(game)?.mods.iter().collect();
+        mods.sort_by_key(|(_, modif)| modif.title.clone());
+        let items: Vec<(String, bool)> = mods
+            .iter()
+            .map(|(_, modif)| (modif.title.clone(), modif.enabled))
+            .collect();
+        let changes: Vec<(String, bool)> = mods
+            .iter()
+            .map(|(key, modif)| ((*key).clone(), modif.enabled))
+            .collect();
+
+        if items.is_empty() {
+            println!("You have no mods installed for that game!");
+            return Ok(Transition::Back);
         }
 
-        println!("Updating mod {} for {}!", modif.title, game.title);
+        let chosen = self.prompt.multi_select_checked(
+            "Select the mods the game loads with space, confirm with enter.",
+            &items,
+        )?;
 
-        let file = self.remote.download_item(item_id).await?;
+        for (index, (key, enabled)) in changes.into_iter().enumerate() {
+            if chosen.contains(&index) != enabled {
+                self.manager
+                    .set_mod_enabled(game, &key, chosen.contains(&index))?;
+            }
+        }
 
-        let zip = zip::ZipArchive::new(Cursor::new(file))?;
+        println!("Enabled mods saved!");
+        Ok(Transition::Back)
+    }
 
-        write_mod(item_id, item_title.clone(), zip, &game.path_mods)?;
+    /// Rolls back, pins or unpins a mod of the game at `game`.
+    pub fn mod_versions(&mut self, game: usize) -> Result<Transition> {
+        let mut mods: Vec<_> = self.manager.game(game)?.mods.iter().collect();
+        mods.sort_by_key(|(_, modif)| modif.title.clone());
+        let keys: Vec<String> = mods.iter().map(|(key, _)| (*key).clone()).collect();
+        let titles: Vec<String> = mods
+            .iter()
+            .map(|(_, modif)| {
+                if modif.pinned {
+                    format!("{} (pinned)", modif.title)
+                } else {
+                    modif.title.clone()
+                }
+            })
+            .collect();
 
-        println!("Updating config file.");
-        modif.time_updated = item_time_updated;
+        if titles.is_empty() {
+            println!("You have no mods installed for that game!");
+            return Ok(Transition::Back);
+        }
 
-        println!("Mod updated!.");
+        let mut items: Vec<&str> = titles.iter().map(String::as_str).collect();
+        items.push("Go back.");
+        let key = match self
+            .prompt
+            .select(&items)?
+            .and_then(|index| keys.get(index))
+        {
+            Some(key) => key.clone(),
+            None => return Ok(Transition::Back),
+        };
 
-        self.config.update_config_file()?;
-        Ok(())
+        let versions = self.manager.mod_versions(game, &key)?;
+        let version_items: Vec<String> = versions
+            .iter()
+            .map(|&time_updated| {
+                format!(
+                    "Install and pin the version from {}.",
+                    format_time(time_updated)
+                )
+            })
+            .collect();
+        let mut items = vec![
+            "Roll back to the previous version.",
+            "Pin the installed version.",
+            "Unpin.",
+        ];
+        items.extend(version_items.iter().map(String::as_str));
+        items.push("Go back.");
+
+        match self.prompt.select(&items)? {
+            Some(0) => {
+                let time_updated = self.manager.rollback_mod(game, &key)?;
+                println!(
+                    "Rolled back to the version from {} and pinned it!",
+                    format_time(time_updated)
+                );
+            }
+            Some(1) => {
+                self.manager.pin_mod(game, &key, None)?;
+                println!("Mod pinned!");
+            }
+            Some(2) => {
+                self.manager.unpin_mod(game, &key)?;
+                println!("Mod unpinned!");
+            }
+            Some(index) if index - 3 < versions.len() => {
+                self.manager
+                    .pin_mod(game, &key, Some(versions[index - 3]))?;
+                println!("Mod pinned!");
+            }
+            _ => {}
+        }
+        Ok(Transition::Back)
     }
 }
@@ -0,0 +1,97 @@
+//! Interface functionality concering `Playset`s.
+
+use anyhow::Result;
+
+use super::{games::validate_title, Interface, Prompt, Transition};
+
+impl<P: Prompt> Interface<P> {
+    /// Creates, clones, switches, renames and deletes playsets of the game at `game`.
+    pub fn playsets(&mut self, game: usize) -> Result<Transition> {
+        let game_info = self.manager.game(game)?;
+        let names: Vec<String> = game_info
+            .playsets
+            .iter()
+            .map(|playset| playset.name.clone())
+            .collect();
+        let titles: Vec<String> = names
+            .iter()
+            .map(|name| {
+                if game_info.playset.as_deref() == Some(name.as_str()) {
+                    format!("{} (active)", name)
+                } else {
+                    name.clone()
+                }
+            })
+            .collect();
+
+        let mut items: Vec<&str> = titles.iter().map(String::as_str).collect();
+        items.push("Create playset from the enabled mods.");
+        items.push("Import playsets from the paradox launcher.");
+        items.push("Export playsets to the paradox launcher.");
+        items.push("Go back.");
+
+        let name = match self.prompt.select(&items)? {
+            Some(index) if index < names.len() => names[index].clone(),
+            Some(index) if index == names.len() => {
+                let name = self.prompt.input(
+                    "Type in the name of the playset. Name can be up to 30 characters long!",
+                    validate_title,
+                )?;
+                self.manager.create_playset(game, name)?;
+                println!("Playset created!");
+                return Ok(Transition::Back);
+            }
+            Some(index) if index == names.len() + 1 => {
+                for name in self.manager.import_launcher_playsets(game)? {
+                    println!("Imported playset {}!", name);
+                }
+                return Ok(Transition::Back);
+            }
+            Some(index) if index == names.len() + 2 => {
+                self.manager.export_launcher_playsets(game)?;
+                println!("Playsets exported to the paradox launcher!");
+                return Ok(Transition::Back);
+            }
+            _ => return Ok(Transition::Back),
+        };
+
+        match self.prompt.select(&[
+            "Switch to it.",
+            "Clone it.",
+            "Delete it.",
+            "Rename it.",
+            "Go back.",
+        ])? {
+            Some(0) => {
+                self.manager.switch_playset(game, &name)?;
+                println!("Switched to playset {}!", name);
+            }
+            Some(1) => {
+                let new_name = self.prompt.input(
+                    "Type in the name of the new playset. Name can be up to 30 characters long!",
+                    validate_title,
+                )?;
+                self.manager.clone_playset(game, &name, new_name)?;
+                println!("Playset cloned!");
+            }
+            Some(2)
+                if self
+                    .prompt
+                    .confirm(&format!("Do you really want to delete {}?", name))? =>
+            {
+                self.manager.delete_playset(game, &name)?;
+                println!("Playset deleted!");
+            }
+            Some(3) => {
+                let new_name = self.prompt.input(
+                    "Type in the new name of the playset. Name can be up to 30 characters long!",
+                    validate_title,
+                )?;
+                self.manager.rename_playset(game, &name, new_name)?;
+                println!("Playset renamed!");
+            }
+            _ => {}
+        }
+        Ok(Transition::Back)
+    }
+}
@@ -0,0 +1,178 @@
+//! Input events for the interface.
+
+#[cfg(test)]
+use std::collections::VecDeque;
+
+#[cfg(test)]
+use anyhow::bail;
+use anyhow::Result;
+use dialoguer::{console::Term, theme::ColorfulTheme, Confirm, Input, MultiSelect, Select};
+
+/// Source of the user input the screens react to.
+///
+/// The terminal implementation is [`Dialoguer`], other implementations can feed screens a fixed sequence of input events.
+pub trait Prompt {
+    /// Lets the user select one of `items`. Returns `None` if the user cancelled.
+    fn select(&mut self, items: &[&str]) -> Result<Option<usize>>;
+
+    /// Lets the user select any number of `items`.
+    fn multi_select(&mut self, prompt: &str, items: &[String]) -> Result<Vec<usize>>;
+
+    /// Lets the user select any number of `items`, each item starts out selected if its flag is set.
+    fn multi_select_checked(
+        &mut self,
+        prompt: &str,
+        items: &[(String, bool)],
+    ) -> Result<Vec<usize>>;
+
+    /// Asks the user for a line of text until `validate` accepts it.
+    fn input(
+        &mut self,
+        prompt: &str,
+        validate: fn(&str) -> Result<(), &'static str>,
+    ) -> Result<String>;
+
+    /// Asks the user a yes or no question.
+    fn confirm(&mut self, prompt: &str) -> Result<bool>;
+}
+
+/// Uses the dialoguer crate to prompt the user on the terminal.
+pub struct Dialoguer;
+
+impl Prompt for Dialoguer {
+    fn select(&mut self, items: &[&str]) -> Result<Option<usize>> {
+        Ok(Select::with_theme(&ColorfulTheme::default())
+            .items(items)
+            .default(0)
+            .interact_on_opt(&Term::stderr())?)
+    }
+
+    fn multi_select(&mut self, prompt: &str, items: &[String]) -> Result<Vec<usize>> {
+        Ok(MultiSelect::with_theme(&ColorfulTheme::default())
+            .with_prompt(prompt)
+            .items(items)
+            .interact()?)
+    }
+
+    fn multi_select_checked(
+        &mut self,
+        prompt: &str,
+        items: &[(String, bool)],
+    ) -> Result<Vec<usize>> {
+        Ok(MultiSelect::with_theme(&ColorfulTheme::default())
+            .with_prompt(prompt)
+            .items_checked(items)
+            .interact()?)
+    }
+
+    fn input(
+        &mut self,
+        prompt: &str,
+        validate: fn(&str) -> Result<(), &'static str>,
+    ) -> Result<String> {
+        Ok(Input::with_theme(&ColorfulTheme::default())
+            .with_prompt(prompt)
+            .validate_with(|input: &String| validate(input))
+            .interact_text()?)
+    }
+
+    fn confirm(&mut self, prompt: &str) -> Result<bool> {
+        Ok(Confirm::with_theme(&ColorfulTheme::default())
+            .with_prompt(prompt)
+            .interact()?)
+    }
+}
+
+/// An answer given by a [`Script`].
+#[cfg(test)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Answer {
+    /// Answers a `select`.
+    Select(Option<usize>),
+    /// Answers a `multi_select` or `multi_select_checked`.
+    MultiSelect(Vec<usize>),
+    /// Answers an `input`.
+    Input(String),
+    /// Answers a `confirm`.
+    Confirm(bool),
+}
+
+/// Replays a fixed list of answers, fails once they are used up or don't match the question.
+#[cfg(test)]
+#[derive(Debug, Default)]
+pub struct Script {
+    /// Answers which weren't given yet.
+    pub answers: VecDeque<Answer>,
+}
+
+#[cfg(test)]
+impl Script {
+    /// Instanciates a new `Script` giving `answers` in order.
+    pub fn new(answers: Vec<Answer>) -> Self {
+        Self {
+            answers: answers.into(),
+        }
+    }
+
+    /// Returns the next answer.
+    fn next(&mut self, question: &str) -> Result<Answer> {
+        match self.answers.pop_front() {
+            Some(answer) => Ok(answer),
+            None => bail!("No answer left for {}!", question),
+        }
+    }
+}
+
+#[cfg(test)]
+impl Prompt for Script {
+    fn select(&mut self, items: &[&str]) -> Result<Option<usize>> {
+        match self.next("select")? {
+            Answer::Select(Some(index)) if index >= items.len() => {
+                bail!("There are only {} items to select!", items.len())
+            }
+            Answer::Select(selection) => Ok(selection),
+            other => bail!("Expected a select, the script answers {:?}!", other),
+        }
+    }
+
+    fn multi_select(&mut self, prompt: &str, _items: &[String]) -> Result<Vec<usize>> {
+        match self.next(prompt)? {
+            Answer::MultiSelect(selection) => Ok(selection),
+            other => bail!("Expected a multi select, the script answers {:?}!", other),
+        }
+    }
+
+    fn multi_select_checked(
+        &mut self,
+        prompt: &str,
+        _items: &[(String, bool)],
+    ) -> Result<Vec<usize>> {
+        match self.next(prompt)? {
+            Answer::MultiSelect(selection) => Ok(selection),
+            other => bail!("Expected a multi select, the script answers {:?}!", other),
+        }
+    }
+
+    fn input(
+        &mut self,
+        prompt: &str,
+        validate: fn(&str) -> Result<(), &'static str>,
+    ) -> Result<String> {
+        match self.next(prompt)? {
+            Answer::Input(input) => {
+                if let Err(error) = validate(&input) {
+                    bail!("{:?} is not valid: {}", input, error);
+                }
+                Ok(input)
+            }
+            other => bail!("Expected an input, the script answers {:?}!", other),
+        }
+    }
+
+    fn confirm(&mut self, prompt: &str) -> Result<bool> {
+        match self.next(prompt)? {
+            Answer::Confirm(confirmed) => Ok(confirmed),
+            other => bail!("Expected a confirm, the script answers {:?}!", other),
+        }
+    }
+}
@@ -0,0 +1,243 @@
+//! Reading and writing the playsets and mods of the paradox launcher.
+//!
+//! Newer launchers keep them in `launcher-v2.sqlite` next to the mod folder. Mods are registered in
+//! the `mods` table, playsets in `playsets` and the mods of each playset in `playsets_mods`.
+
+use std::collections::HashMap;
+
+use anyhow::{bail, Result};
+use rusqlite::{params, Connection, OptionalExtension};
+use uuid::Uuid;
+
+use crate::enabled::user_dir;
+
+/// Name of the database of the paradox launcher.
+const DATABASE: &str = "launcher-v2.sqlite";
+
+/// A playset of the launcher.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LauncherPlayset {
+    /// Name of the playset.
+    pub name: String,
+    /// Whether the launcher starts the game with this playset.
+    pub active: bool,
+    /// Mods of the playset in load order.
+    pub mods: Vec<LauncherMod>,
+}
+
+/// A mod of a launcher playset.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LauncherMod {
+    /// Entry of the mod's `.mod` file in the game's list of enabled mods, like `mod/123.mod`.
+    pub game_registry_id: Option<String>,
+    /// Id of the mod in the steam workshop.
+    pub steam_id: Option<String>,
+    /// Name of the mod.
+    pub name: Option<String>,
+    /// Whether the mod is enabled in the playset.
+    pub enabled: bool,
+}
+
+/// A mod to register in the launcher.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Registration {
+    /// Entry of the mod's `.mod` file in the game's list of enabled mods, like `mod/123.mod`.
+    pub game_registry_id: String,
+    /// Id of the mod in the steam workshop.
+    pub steam_id: Option<String>,
+    /// Name of the mod.
+    pub name: String,
+    /// Folder the mod is installed in.
+    pub dir_path: String,
+    /// Time of the mod's last update in unix time.
+    pub time_updated: u64,
+}
+
+/// The launcher database of a game.
+pub struct Launcher {
+    /// Connection to `launcher-v2.sqlite`.
+    connection: Connection,
+}
+
+impl Launcher {
+    /// Opens the launcher database of the game with the mod folder `path_mods`.
+    pub fn open(path_mods: &str) -> Result<Self> {
+        let path = user_dir(path_mods).join(DATABASE);
+        if !path.exists() {
+            bail!(
+                "{} does not exist, start the paradox launcher once to create it!",
+                path.display()
+            );
+        }
+        Ok(Self {
+            connection: Connection::open(path)?,
+        })
+    }
+
+    /// Reads all playsets which weren't deleted in the launcher.
+    pub fn playsets(&self) -> Result<Vec<LauncherPlayset>> {
+        let mut statement = self.connection.prepare(
+            "SELECT id, name, isActive FROM playsets WHERE isRemoved = 0 OR isRemoved IS NULL ORDER BY name",
+        )?;
+        let rows = statement
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, Option<bool>>(2)?.unwrap_or(false),
+                ))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        let mut mods_statement = self.connection.prepare(
+            "SELECT mods.gameRegistryId, mods.steamId, COALESCE(mods.displayName, mods.name), playsets_mods.enabled \
+             FROM playsets_mods JOIN mods ON playsets_mods.modId = mods.id \
+             WHERE playsets_mods.playsetId = ?1 ORDER BY playsets_mods.position",
+        )?;
+        let mut playsets = Vec::new();
+        for (id, name, active) in rows {
+            let mods = mods_statement
+                .query_map(params![id], |row| {
+                    Ok(LauncherMod {
+                        game_registry_id: row.get(0)?,
+                        steam_id: row.get(1)?,
+                        name: row.get(2)?,
+                        enabled: row.get::<_, Option<bool>>(3)?.unwrap_or(true),
+                    })
+                })?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            playsets.push(LauncherPlayset { name, active, mods });
+        }
+        Ok(playsets)
+    }
+
+    /// Registers the mods of `registrations` and replaces the launcher playsets with the names of `playsets`.
+    ///
+    /// The mods of `playsets` are identified by their `game_registry_id`, mods which aren't registered stay in the launcher playsets.
+    /// Everything is written in one transaction.
+    pub fn export(
+        &mut self,
+        registrations: &[Registration],
+        playsets: &[LauncherPlayset],
+    ) -> Result<()> {
+        let transaction = self.connection.transaction()?;
+
+        let mut ids = HashMap::new();
+        for registration in registrations {
+            let existing: Option<String> = transaction
+                .query_row(
+                    "SELECT id FROM mods WHERE gameRegistryId = ?1",
+                    params![registration.game_registry_id],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            let id = match existing {
+                Some(id) => {
+                    let _rows = transaction.execute(
+                        "UPDATE mods SET name = ?2, displayName = ?2, dirPath = ?3, timeUpdated = ?4 WHERE id = ?1",
+                        params![
+                            id,
+                            registration.name,
+                            registration.dir_path,
+                            registration.time_updated as i64
+                        ],
+                    )?;
+                    id
+                }
+                None => {
+                    // Steam ids are unique, a copy subscribed through steam keeps its own.
+                    let steam_taken: Option<String> = transaction
+                        .query_row(
+                            "SELECT id FROM mods WHERE steamId = ?1",
+                            params![registration.steam_id],
+                            |row| row.get(0),
+                        )
+                        .optional()?;
+                    let steam_id = registration
+                        .steam_id
+                        .as_ref()
+                        .filter(|_| steam_taken.is_none());
+
+                    let id = Uuid::new_v4().to_string();
+                    let _rows = transaction.execute(
+                        "INSERT INTO mods (id, steamId, gameRegistryId, name, displayName, dirPath, status, source, timeUpdated) \
+                         VALUES (?1, ?2, ?3, ?4, ?4, ?5, 'ready_to_play', 'local', ?6)",
+                        params![
+                            id,
+                            steam_id,
+                            registration.game_registry_id,
+                            registration.name,
+                            registration.dir_path,
+                            registration.time_updated as i64
+                        ],
+                    )?;
+                    id
+                }
+            };
+            let _old = ids.insert(registration.game_registry_id.clone(), id);
+        }
+
+        if playsets.iter().any(|playset| playset.active) {
+            let _rows = transaction.execute("UPDATE playsets SET isActive = 0", [])?;
+        }
+        for playset in playsets {
+            let existing: Option<String> = transaction
+                .query_row(
+                    "SELECT id FROM playsets WHERE name = ?1 AND (isRemoved = 0 OR isRemoved IS NULL)",
+                    params![playset.name],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            // Mods only the launcher knows stay in its playsets, in front of the exported ones.
+            let mut kept: Vec<(String, bool)> = Vec::new();
+            let playset_id = match existing {
+                Some(id) => {
+                    let _rows = transaction.execute(
+                        "UPDATE playsets SET isActive = ?2, updatedOn = strftime('%Y-%m-%d %H:%M:%f +00:00', 'now') WHERE id = ?1",
+                        params![id, playset.active],
+                    )?;
+                    kept = transaction
+                        .prepare(
+                            "SELECT modId, enabled FROM playsets_mods WHERE playsetId = ?1 ORDER BY position",
+                        )?
+                        .query_map(params![id], |row| {
+                            Ok((row.get(0)?, row.get::<_, Option<bool>>(1)?.unwrap_or(true)))
+                        })?
+                        .collect::<rusqlite::Result<Vec<_>>>()?;
+                    kept.retain(|(mod_id, _)| !ids.values().any(|id| id == mod_id));
+                    let _rows = transaction.execute(
+                        "DELETE FROM playsets_mods WHERE playsetId = ?1",
+                        params![id],
+                    )?;
+                    id
+                }
+                None => {
+                    let id = Uuid::new_v4().to_string();
+                    let _rows = transaction.execute(
+                        "INSERT INTO playsets (id, name, isActive, loadOrder, createdOn, isRemoved) \
+                         VALUES (?1, ?2, ?3, 'custom', strftime('%Y-%m-%d %H:%M:%f +00:00', 'now'), 0)",
+                        params![id, playset.name, playset.active],
+                    )?;
+                    id
+                }
+            };
+
+            let exported = playset.mods.iter().filter_map(|playset_mod| {
+                playset_mod
+                    .game_registry_id
+                    .as_ref()
+                    .and_then(|registry_id| ids.get(registry_id))
+                    .map(|mod_id| (mod_id.clone(), playset_mod.enabled))
+            });
+            for (position, (mod_id, enabled)) in kept.into_iter().chain(exported).enumerate() {
+                let _rows = transaction.execute(
+                    "INSERT INTO playsets_mods (playsetId, modId, enabled, position) VALUES (?1, ?2, ?3, ?4)",
+                    params![playset_id, mod_id, enabled, position as i64],
+                )?;
+            }
+        }
+
+        transaction.commit()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,211 @@
+//! This library is for people who own the gog version of paradox games but still want to use mods from steam.
+//!
+//! The [`ModManager`] holds the configuration and offers all operations on games and mods.
+//! The `pdx_mod_manager` binary is an interactive and a command line front-end for it.
+//!
+//! This is still a very early version, use at your own risk.
+//!
+//! Todos:
+//! - clean up documentation and rethink the whole struct and program structure
+//! - add actual errorhandling
+//! - let the program find already installed mods on its own
+//! - improve interface
+
+pub mod backend;
+pub mod cancel;
+pub mod conflicts;
+pub mod dependencies;
+pub mod descriptor;
+pub mod discovery;
+pub mod enabled;
+pub mod filesystem;
+pub mod git;
+pub mod launcher;
+mod manager;
+pub mod progress;
+pub mod remote;
+pub mod source;
+
+use std::collections::HashMap;
+
+use backend::BackendConfig;
+use discovery::KnownGame;
+pub use manager::ModManager;
+use serde::{Deserialize, Serialize};
+use source::Source;
+
+/// This struct saves the configuration for this program and is used for toml deserialization and serialization.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct Config {
+    /// Maximum number of mods downloaded at the same time.
+    #[serde(default = "default_concurrency")]
+    pub concurrency: usize,
+    /// How often a failed request of a download is retried before the mod is given up.
+    #[serde(default = "default_retries")]
+    pub retries: u32,
+    /// Seconds to wait for the backend to prepare a download before the mod is given up.
+    #[serde(default = "default_max_wait")]
+    pub max_wait: u64,
+    /// How many earlier versions of each mod are archived so they can be installed again.
+    #[serde(default = "default_kept_versions")]
+    pub kept_versions: usize,
+    /// All configured `Game`s.
+    pub games: Vec<Game>,
+    /// Service mods are downloaded from.
+    #[serde(default)]
+    pub backend: BackendConfig,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            concurrency: default_concurrency(),
+            retries: default_retries(),
+            max_wait: default_max_wait(),
+            kept_versions: default_kept_versions(),
+            games: Vec::new(),
+            backend: BackendConfig::default(),
+        }
+    }
+}
+
+/// Default for `Config::concurrency`.
+const fn default_concurrency() -> usize {
+    4
+}
+
+/// Default for `Config::retries`.
+const fn default_retries() -> u32 {
+    3
+}
+
+/// Default for `Config::max_wait`.
+const fn default_max_wait() -> u64 {
+    600
+}
+
+/// Default for `Config::kept_versions`.
+const fn default_kept_versions() -> usize {
+    3
+}
+
+/// Configured games.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct Game {
+    /// Name of the game.
+    pub title: String,
+    /// Path where mods are installed for this game.
+    pub path_mods: String,
+    /// Steam app id of the game, used to check that workshop items belong to it.
+    #[serde(default)]
+    pub app_id: Option<u64>,
+    /// Name of the active `Playset`, whose mods are enabled in the game.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub playset: Option<String>,
+    /// Configured `Mod`s.
+    pub mods: HashMap<String, Mod>,
+    /// Workshop collections installed for this game.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub collections: Vec<Collection>,
+    /// Named sets of mods which are enabled together.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub playsets: Vec<Playset>,
+}
+
+/// Workshop collections whose members are kept installed.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct Collection {
+    /// Name of the collection.
+    pub title: String,
+    /// Id of the collection.
+    pub id: u64,
+    /// Ids of the items in the collection when it was last installed or updated.
+    pub members: Vec<u64>,
+    /// Ids of the members the user chose not to install.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub excluded: Vec<u64>,
+}
+
+/// Named set of mods which are enabled together, in load order.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct Playset {
+    /// Name of the playset.
+    pub name: String,
+    /// Mods of the playset in load order.
+    pub mods: Vec<PlaysetMod>,
+}
+
+/// A mod of a `Playset`.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+pub struct PlaysetMod {
+    /// Id of the mod.
+    pub id: u64,
+    /// Whether the mod is enabled in the playset.
+    pub enabled: bool,
+}
+
+impl Game {
+    /// Returns the configured steam app id or the one of the known game with the same name.
+    pub fn app_id(&self) -> Option<u64> {
+        self.app_id
+            .or_else(|| KnownGame::by_title(&self.title).map(|game| game.app_id))
+    }
+}
+
+/// Configured mods.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct Mod {
+    /// Name of the mod.
+    pub title: String,
+    /// Id of the mod.
+    pub id: u64,
+    /// Time of the games last update in unix time.
+    pub time_updated: u64,
+    /// Whether the mod was only installed because other mods require it.
+    #[serde(default)]
+    pub dependency: bool,
+    /// Whether the mod stays at its installed version instead of being updated.
+    #[serde(default)]
+    pub pinned: bool,
+    /// Whether the game loads the mod, mirrors the game's list of enabled mods.
+    #[serde(default)]
+    pub enabled: bool,
+    /// Ids of the installed mods which require this mod.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub required_by: Vec<u64>,
+    /// Ids of the mods this mod is loaded after, in addition to the dependencies in its descriptor.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub load_after: Vec<u64>,
+    /// Id of the collection the mod was installed from.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub collection: Option<u64>,
+    /// Version of the mod if its source has one, like the commit of a git mod.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// SHA-256 hash of the installed archive of a mod downloaded from a url.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+    /// Where the mod was installed from.
+    #[serde(default, skip_serializing_if = "Source::is_workshop")]
+    pub source: Source,
+}
+
+impl Mod {
+    /// Instanciates a new `Mod` which was installed on its own.
+    pub fn new(id: u64, title: String, time_updated: u64, source: Source) -> Self {
+        Self {
+            title,
+            id,
+            time_updated,
+            dependency: false,
+            pinned: false,
+            enabled: false,
+            required_by: Vec::new(),
+            load_after: Vec::new(),
+            collection: None,
+            version: None,
+            sha256: None,
+            source,
+        }
+    }
+}
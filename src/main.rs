@@ -1,59 +1,206 @@
-//! This program is for people who own the gog version of paradox games but still want to use mods from steam.
-//!
-//! This is still a very early version, use at your own risk.
-//!
-//! Todos:
-//! - clean up documentation and rethink the whole struct and program structure
-//! - add actual errorhandling
-//! - add funcionality for users to cancel operations
-//! - let the program find mods and games on its own and configure them automatically
-//! - add progress bars for operations
-//! - improve interface
-
-mod filesystem;
+//! Interactive and command line front-end for the `pdx_mod_manager` library.
+
+mod cli;
 mod interface;
-mod remote;
 
-use std::collections::HashMap;
+use std::{
+    future::Future,
+    io::BufRead,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
 
 use anyhow::Result;
+use cli::Args;
 use interface::Interface;
-use serde::{Deserialize, Serialize};
+use pdx_mod_manager::{
+    cancel::{CancellationToken, Downloads, RunningDownload},
+    ModManager,
+};
+use structopt::StructOpt;
+use tokio::sync::mpsc::{self, UnboundedSender};
+
+/// Hint how to cancel single downloads, printed when an update starts.
+const CANCEL_HINT: &str =
+    "Press enter to list the running downloads and enter the number of one to cancel it.";
+
+/// Runs `operation`, cancelling `token` when the user presses Ctrl+C.
+///
+/// Pressing Ctrl+C a second time quits immediately.
+pub(crate) async fn cancel_on_ctrl_c<T>(
+    token: CancellationToken,
+    operation: impl Future<Output = T>,
+) -> T {
+    tokio::pin!(operation);
+    loop {
+        tokio::select! {
+            result = &mut operation => return result,
+            signal = tokio::signal::ctrl_c() => {
+                if signal.is_err() {
+                    return operation.await;
+                }
+                if token.is_cancelled() {
+                    std::process::exit(130);
+                }
+                println!("Cancelling, press Ctrl+C again to quit immediately.");
+                token.cancel();
+            }
+        }
+    }
+}
 
-/// This struct saves the configuration for this program and is used for toml deserialization and serialization.
-#[derive(Debug, Deserialize, Serialize, Clone)]
-pub struct Config {
-    /// All configured `Game`s.
-    games: Vec<Game>,
+/// Runs `operation`, letting the user cancel single `downloads` from the terminal.
+///
+/// Pressing enter lists the running downloads, entering the number of one cancels it. Input is only read while
+/// `operation` runs, so later prompts get all of it.
+pub(crate) async fn cancel_downloads_on_input<T>(
+    downloads: Downloads,
+    operation: impl Future<Output = T>,
+) -> T {
+    let (sender, mut lines) = mpsc::unbounded_channel();
+    let stop = Arc::new(AtomicBool::new(false));
+    let reader = tokio::task::spawn_blocking({
+        let stop = stop.clone();
+        move || read_lines(&stop, sender)
+    });
+    println!("{}", CANCEL_HINT);
+
+    let mut operation = Box::pin(operation);
+    let mut shown = Vec::new();
+    let result = loop {
+        tokio::select! {
+            result = &mut operation => break result,
+            Some(line) = lines.recv() => println!("{}", answer_line(&downloads, &mut shown, &line)),
+        }
+    };
+    stop.store(true, Ordering::Relaxed);
+    let _reader = reader.await;
+    result
+}
+
+/// Answers a `line` the user entered during an update.
+///
+/// An empty line lists the running downloads and remembers them in `shown`, a number cancels that download of `shown`.
+fn answer_line(downloads: &Downloads, shown: &mut Vec<RunningDownload>, line: &str) -> String {
+    let line = line.trim();
+    if line.is_empty() {
+        *shown = downloads.running();
+        if shown.is_empty() {
+            return "No downloads are running.".to_string();
+        }
+        return shown
+            .iter()
+            .enumerate()
+            .map(|(index, download)| {
+                format!("{}: {} ({})", index + 1, download.title, download.item_id)
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+    }
+    match line
+        .parse::<usize>()
+        .ok()
+        .and_then(|number| shown.get(number.checked_sub(1)?))
+    {
+        Some(download) if downloads.cancel(download.game_selection, download.item_id) => {
+            format!("Cancelling the download of {}.", download.title)
+        }
+        Some(download) => format!("The download of {} is not running anymore.", download.title),
+        None => CANCEL_HINT.to_string(),
+    }
 }
 
-/// Configured games.
-#[derive(Debug, Deserialize, Serialize, Clone)]
-pub struct Game {
-    /// Name of the game.
-    title: String,
-    /// Path where mods are installed for this game.
-    path_mods: String,
-    /// Configured `Mod`s.
-    mods: HashMap<String, Mod>,
+/// Sends the lines of the standard input to `sender` until `stop` is set or the input ends.
+fn read_lines(stop: &AtomicBool, sender: UnboundedSender<String>) {
+    let stdin = std::io::stdin();
+    while !stop.load(Ordering::Relaxed) {
+        if !input_ready() {
+            continue;
+        }
+        let mut line = String::new();
+        match stdin.lock().read_line(&mut line) {
+            Ok(0) | Err(_) => return,
+            Ok(_) => {
+                if sender.send(line).is_err() {
+                    return;
+                }
+            }
+        }
+    }
 }
 
-/// Configured mods.
-#[derive(Debug, Deserialize, Serialize, Clone)]
-struct Mod {
-    /// Name of the mod.
-    title: String,
-    /// Id of the mod.
-    id: u64,
-    /// Time of the games last update in unix time.
-    time_updated: u64,
+/// Waits up to 100 milliseconds for input on a terminal, returns whether a line can be read without blocking.
+#[cfg(unix)]
+fn input_ready() -> bool {
+    let mut stdin = libc::pollfd {
+        fd: libc::STDIN_FILENO,
+        events: libc::POLLIN,
+        revents: 0,
+    };
+    // SAFETY: `stdin` is a valid pollfd and the count of one matches it.
+    unsafe { libc::isatty(libc::STDIN_FILENO) == 1 && libc::poll(&mut stdin, 1, 100) > 0 }
+}
+
+/// Reading input without blocking is only supported on unix, elsewhere single downloads can't be cancelled.
+#[cfg(not(unix))]
+fn input_ready() -> bool {
+    std::thread::sleep(std::time::Duration::from_millis(100));
+    false
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let mut interface = Interface::new()?;
+    let args = Args::from_args();
+    let mut manager = ModManager::new()?;
 
-    interface.show_main_menu().await?;
+    if let Some(command) = args.command {
+        let token = manager.cancellation();
+        cancel_on_ctrl_c(token, command.run(&mut manager)).await?;
+    } else {
+        Interface::new(manager).run().await?;
+    }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use pdx_mod_manager::cancel::{CancellationToken, Downloads};
+
+    use super::{answer_line, CANCEL_HINT};
+
+    #[test]
+    fn entered_numbers_cancel_the_listed_downloads() {
+        let parent = CancellationToken::new();
+        let downloads = Downloads::default();
+        let mut shown = Vec::new();
+        assert_eq!(
+            answer_line(&downloads, &mut shown, "\n"),
+            "No downloads are running."
+        );
+
+        let first = downloads.start(0, 1, "First", &parent);
+        let second = downloads.start(1, 1, "Second", &parent);
+        assert_eq!(
+            answer_line(&downloads, &mut shown, "\n"),
+            "1: First (1)\n2: Second (1)"
+        );
+        assert_eq!(
+            answer_line(&downloads, &mut shown, "2\n"),
+            "Cancelling the download of Second."
+        );
+        assert!(!first.is_cancelled());
+        assert!(second.is_cancelled());
+
+        downloads.finish(0, 1);
+        assert_eq!(
+            answer_line(&downloads, &mut shown, "1"),
+            "The download of First is not running anymore."
+        );
+        assert_eq!(answer_line(&downloads, &mut shown, "3"), CANCEL_HINT);
+        assert_eq!(answer_line(&downloads, &mut shown, "0"), CANCEL_HINT);
+        assert_eq!(answer_line(&downloads, &mut shown, "first"), CANCEL_HINT);
+    }
+}
@@ -0,0 +1,41 @@
+//! Finding files the enabled mods of a game overwrite in each other.
+
+use std::path::PathBuf;
+
+use anyhow::Result;
+
+use super::ModManager;
+use crate::{
+    conflicts::{find_conflicts, Conflicts},
+    descriptor::Descriptor,
+    enabled::user_dir,
+};
+
+impl ModManager {
+    /// Finds the files provided by more than one enabled mod of the game at `game_selection`.
+    ///
+    /// The mods are taken in load order, so the last mod of each conflict wins. Mod folders are found through
+    /// the `path` of their `.mod` file, mods packed in archives or without a folder are left out.
+    pub fn conflicts(&mut self, game_selection: usize) -> Result<Conflicts> {
+        let order = self.load_order(game_selection)?;
+        let user_dir = user_dir(&self.game(game_selection)?.path_mods);
+
+        let mut folders: Vec<(String, PathBuf)> = Vec::new();
+        for (entry, title) in order {
+            let folder = Descriptor::read(&user_dir.join(&entry))
+                .ok()
+                .and_then(|descriptor| descriptor.path)
+                .map(|path| user_dir.join(path));
+            match folder {
+                Some(folder) if folder.is_dir() => folders.push((title, folder)),
+                _ => println!("Could not find the folder of {}, it is left out.", title),
+            }
+        }
+
+        let mods: Vec<_> = folders
+            .iter()
+            .map(|(title, folder)| (title.clone(), folder.as_path()))
+            .collect();
+        find_conflicts(&mods)
+    }
+}
@@ -0,0 +1,103 @@
+//! Keeping the enabled state of mods in sync with the list of mods the game loads.
+
+use anyhow::{bail, Result};
+
+use super::ModManager;
+use crate::enabled::{enabled_files, entry_exists, mod_entry};
+
+impl ModManager {
+    /// Reads which mods of the game at `game_selection` are enabled from the game's list of enabled mods.
+    ///
+    /// Returns the list in load order, which is empty if the game has none yet.
+    pub fn read_enabled_mods(&mut self, game_selection: usize) -> Result<Vec<String>> {
+        let path_mods = self.game(game_selection)?.path_mods.clone();
+        let mut entries = None;
+        for file in enabled_files(&path_mods) {
+            entries = file.read()?;
+            if entries.is_some() {
+                break;
+            }
+        }
+
+        let entries = match entries {
+            Some(entries) => entries,
+            None => return Ok(Vec::new()),
+        };
+        for (key, item_mod) in &mut self.game_mut(game_selection)?.mods {
+            item_mod.enabled = entries.contains(&mod_entry(&path_mods, key));
+        }
+        Ok(entries)
+    }
+
+    /// Enables or disables the mod with the configuration key `key` in the game's list of enabled mods.
+    pub fn set_mod_enabled(
+        &mut self,
+        game_selection: usize,
+        key: &str,
+        enabled: bool,
+    ) -> Result<()> {
+        let game = self.game(game_selection)?;
+        if !game.mods.contains_key(key) {
+            bail!("Mod {} is not installed for {}!", key, game.title);
+        }
+        self.update_enabled_mods(game_selection, Some((key, enabled)))
+    }
+
+    /// Writes the game's list of enabled mods after enabling or disabling the mod of `change`.
+    ///
+    /// The states of all other mods are read from the list first, so changes from the launcher are kept.
+    /// Mods enabled by the list keep their place, newly enabled ones are added at the end.
+    /// Entries of mods which aren't installed anymore are dropped.
+    pub(super) fn update_enabled_mods(
+        &mut self,
+        game_selection: usize,
+        change: Option<(&str, bool)>,
+    ) -> Result<()> {
+        let current = self.read_enabled_mods(game_selection)?;
+        let game = self.game_mut(game_selection)?;
+        if let Some((key, enabled)) = change {
+            if let Some(item_mod) = game.mods.get_mut(key) {
+                item_mod.enabled = enabled;
+            }
+        }
+
+        let path_mods = game.path_mods.clone();
+        let mut mods: Vec<_> = game.mods.iter().collect();
+        mods.sort_by_key(|(_, item_mod)| item_mod.title.clone());
+        let managed: Vec<(String, bool)> = mods
+            .into_iter()
+            .map(|(key, item_mod)| (mod_entry(&path_mods, key), item_mod.enabled))
+            .collect();
+
+        let mut entries: Vec<String> = Vec::new();
+        for entry in current {
+            let keep = match managed.iter().find(|(managed, _)| *managed == entry) {
+                Some((_, enabled)) => *enabled,
+                None => entry_exists(&path_mods, &entry),
+            };
+            if keep && !entries.contains(&entry) {
+                entries.push(entry);
+            }
+        }
+        for (entry, enabled) in managed {
+            if enabled && !entries.contains(&entry) {
+                entries.push(entry);
+            }
+        }
+
+        self.write_enabled_mods(game_selection, &entries)
+    }
+
+    /// Writes `entries` as the game's list of enabled mods and saves them in the active playset.
+    pub(super) fn write_enabled_mods(
+        &mut self,
+        game_selection: usize,
+        entries: &[String],
+    ) -> Result<()> {
+        for file in enabled_files(&self.game(game_selection)?.path_mods) {
+            file.write(entries)?;
+        }
+        self.capture_playset(game_selection, entries)?;
+        self.save_config()
+    }
+}
@@ -0,0 +1,393 @@
+//! Moving playsets between this program and the paradox launcher.
+
+use std::path::Path;
+
+use anyhow::Result;
+
+use super::ModManager;
+use crate::{
+    enabled::mod_entry,
+    launcher::{Launcher, LauncherMod, LauncherPlayset, Registration},
+    Playset, PlaysetMod,
+};
+
+impl ModManager {
+    /// Imports the playsets of the paradox launcher into the game at `game_selection`.
+    ///
+    /// Playsets with the same name are replaced, mods of the launcher which aren't installed here are left out.
+    /// If the active playset is replaced, its mods are enabled. Returns the names of the imported playsets.
+    pub fn import_launcher_playsets(&mut self, game_selection: usize) -> Result<Vec<String>> {
+        let entries = self.read_enabled_mods(game_selection)?;
+        self.capture_playset(game_selection, &entries)?;
+
+        let game = self.game_mut(game_selection)?;
+        let launcher_playsets = Launcher::open(&game.path_mods)?.playsets()?;
+        let mut names = Vec::new();
+        let mut replaced_active = None;
+
+        for launcher_playset in launcher_playsets {
+            let mut mods: Vec<PlaysetMod> = Vec::new();
+            let mut skipped = 0;
+            for launcher_mod in &launcher_playset.mods {
+                let found = game.mods.values().find(|item_mod| {
+                    launcher_mod.game_registry_id.as_deref()
+                        == Some(mod_entry(&game.path_mods, &item_mod.id.to_string()).as_str())
+                        || (item_mod.source.is_workshop()
+                            && launcher_mod.steam_id.as_deref()
+                                == Some(item_mod.id.to_string().as_str()))
+                });
+                match found {
+                    Some(item_mod)
+                        if !mods.iter().any(|playset_mod| playset_mod.id == item_mod.id) =>
+                    {
+                        mods.push(PlaysetMod {
+                            id: item_mod.id,
+                            enabled: launcher_mod.enabled,
+                        });
+                    }
+                    Some(_) => {}
+                    None => skipped += 1,
+                }
+            }
+            if skipped > 0 {
+                println!(
+                    "{} mods of {} are not installed here and were left out.",
+                    skipped, launcher_playset.name
+                );
+            }
+
+            let name = launcher_playset.name;
+            match game
+                .playsets
+                .iter_mut()
+                .find(|playset| playset.name.eq_ignore_ascii_case(&name))
+            {
+                Some(playset) => {
+                    if game.playset.as_deref() == Some(playset.name.as_str()) {
+                        replaced_active = Some(playset.name.clone());
+                    }
+                    playset.mods = mods;
+                }
+                None => game.playsets.push(Playset {
+                    name: name.clone(),
+                    mods,
+                }),
+            }
+            names.push(name);
+        }
+
+        self.save_config()?;
+        if let Some(active) = replaced_active {
+            self.switch_playset(game_selection, &active)?;
+        }
+        Ok(names)
+    }
+
+    /// Registers all mods of the game at `game_selection` in the paradox launcher and writes the playsets there.
+    ///
+    /// Launcher playsets with the same name are replaced, the active playset becomes the launcher's active one.
+    pub fn export_launcher_playsets(&mut self, game_selection: usize) -> Result<()> {
+        let entries = self.read_enabled_mods(game_selection)?;
+        self.capture_playset(game_selection, &entries)?;
+
+        let game = self.game(game_selection)?;
+        let registrations: Vec<Registration> = game
+            .mods
+            .iter()
+            .map(|(key, item_mod)| Registration {
+                game_registry_id: mod_entry(&game.path_mods, key),
+                steam_id: if item_mod.source.is_workshop() {
+                    Some(key.clone())
+                } else {
+                    None
+                },
+                name: item_mod.title.clone(),
+                dir_path: Path::new(game.path_mods.trim())
+                    .join(key)
+                    .to_string_lossy()
+                    .into_owned(),
+                time_updated: item_mod.time_updated,
+            })
+            .collect();
+        let playsets: Vec<LauncherPlayset> = game
+            .playsets
+            .iter()
+            .map(|playset| LauncherPlayset {
+                name: playset.name.clone(),
+                active: game.playset.as_deref() == Some(playset.name.as_str()),
+                mods: playset
+                    .mods
+                    .iter()
+                    .map(|playset_mod| LauncherMod {
+                        game_registry_id: Some(mod_entry(
+                            &game.path_mods,
+                            &playset_mod.id.to_string(),
+                        )),
+                        steam_id: None,
+                        name: None,
+                        enabled: playset_mod.enabled,
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        Launcher::open(&game.path_mods)?.export(&registrations, &playsets)?;
+        self.save_config()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::PathBuf};
+
+    use rusqlite::{params, Connection};
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::{source::Source, Mod};
+
+    /// Tables of the launcher database, as the paradox launcher creates them.
+    const SCHEMA: &str = include_str!("../../fixtures/launcher-v2.sql");
+
+    /// Creates the launcher database of the test game with the launcher's tables and `rows`.
+    fn launcher(dir: &TempDir, rows: &str) -> PathBuf {
+        let path = dir.path().join("Stellaris").join("launcher-v2.sqlite");
+        fs::create_dir_all(dir.path().join("Stellaris")).unwrap();
+        let connection = Connection::open(&path).unwrap();
+        connection.execute_batch(SCHEMA).unwrap();
+        connection.execute_batch(rows).unwrap();
+        path
+    }
+
+    /// Instantiates a manager with the workshop mods 1 and 2 and the local mod 3.
+    fn manager(dir: &TempDir) -> ModManager {
+        let mut manager = ModManager::for_test(dir.path());
+        let game = manager.game_mut(0).unwrap();
+        for item_mod in [
+            Mod::new(1, String::from("First"), 10, Source::Workshop),
+            Mod::new(2, String::from("Second"), 20, Source::Workshop),
+            Mod::new(
+                3,
+                String::from("Third"),
+                30,
+                Source::Local {
+                    path: String::from("/third"),
+                },
+            ),
+        ] {
+            let _old = game.mods.insert(item_mod.id.to_string(), item_mod);
+        }
+        manager
+    }
+
+    /// Returns a playset called `name` with the ids and enabled flags of `mods`.
+    fn playset(name: &str, mods: &[(u64, bool)]) -> Playset {
+        Playset {
+            name: String::from(name),
+            mods: mods
+                .iter()
+                .map(|&(id, enabled)| PlaysetMod { id, enabled })
+                .collect(),
+        }
+    }
+
+    /// Reads the mods of the launcher playset `playset_id` as name, steam id and enabled in load order.
+    fn launcher_mods(
+        connection: &Connection,
+        playset_id: &str,
+    ) -> Vec<(String, Option<String>, bool)> {
+        connection
+            .prepare(
+                "SELECT mods.name, mods.steamId, playsets_mods.enabled FROM playsets_mods \
+                 JOIN mods ON playsets_mods.modId = mods.id WHERE playsetId = ?1 ORDER BY position",
+            )
+            .unwrap()
+            .query_map(params![playset_id], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+            })
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn launcher_playsets_are_imported() {
+        let dir = TempDir::new().unwrap();
+        let _path = launcher(
+            &dir,
+            "INSERT INTO mods (id, steamId, gameRegistryId, name, status, source) VALUES
+                ('a', '999', 'mod/ugc_999.mod', 'Launcher only', 'ready_to_play', 'steam'),
+                ('b', NULL, 'mod/1.mod', 'First', 'ready_to_play', 'local'),
+                ('c', '2', 'mod/ugc_2.mod', 'Second', 'ready_to_play', 'steam'),
+                ('d', NULL, 'mod/3.mod', 'Third', 'ready_to_play', 'local');
+             INSERT INTO playsets (id, name, isActive, loadOrder, createdOn, isRemoved) VALUES
+                ('p1', 'Main', 1, 'custom', '2024-01-01 00:00:00.000 +00:00', 0),
+                ('p2', 'Deleted', 0, 'custom', '2024-01-01 00:00:00.000 +00:00', 1);
+             INSERT INTO playsets_mods (playsetId, modId, enabled, position) VALUES
+                ('p1', 'a', 1, 0), ('p1', 'd', 0, 1), ('p1', 'b', 1, 2), ('p1', 'c', 1, 3),
+                ('p2', 'b', 1, 0);",
+        );
+        let mut manager = manager(&dir);
+
+        let names = manager.import_launcher_playsets(0).unwrap();
+        assert_eq!(names, vec![String::from("Main")]);
+        // The launcher only mod is left out, the steam copy of mod 2 is found by its steam id.
+        assert_eq!(
+            manager.game(0).unwrap().playsets,
+            vec![playset("Main", &[(3, false), (1, true), (2, true)])]
+        );
+    }
+
+    #[test]
+    fn playsets_are_exported_and_imported_again() {
+        let dir = TempDir::new().unwrap();
+        let path = launcher(
+            &dir,
+            "INSERT INTO mods (id, steamId, gameRegistryId, name, status, source, timeUpdated) VALUES
+                ('a', '999', 'mod/ugc_999.mod', 'Launcher only', 'ready_to_play', 'steam', 1),
+                ('b', NULL, 'mod/1.mod', 'Old name', 'ready_to_play', 'local', 5),
+                ('c', '2', 'mod/ugc_2.mod', 'Steam copy', 'ready_to_play', 'steam', 1);
+             INSERT INTO playsets (id, name, isActive, loadOrder, createdOn, isRemoved) VALUES
+                ('p0', 'Main', 1, 'custom', '2024-01-01 00:00:00.000 +00:00', 1),
+                ('p1', 'Main', 0, 'custom', '2024-01-01 00:00:00.000 +00:00', 0),
+                ('p3', 'Launcher', 1, 'custom', '2024-01-01 00:00:00.000 +00:00', 0);
+             INSERT INTO playsets_mods (playsetId, modId, enabled, position) VALUES
+                ('p1', 'a', 0, 0), ('p1', 'b', 1, 1);",
+        );
+        let exported = vec![
+            playset("Main", &[(2, true), (1, true), (3, false)]),
+            playset("Other", &[(1, true)]),
+        ];
+        let mut manager = manager(&dir);
+        let game = manager.game_mut(0).unwrap();
+        game.playsets = exported.clone();
+        game.playset = Some(String::from("Main"));
+        fs::write(
+            dir.path().join("Stellaris").join("dlc_load.json"),
+            r#"{"enabled_mods":["mod/2.mod","mod/1.mod"],"disabled_dlcs":[]}"#,
+        )
+        .unwrap();
+
+        manager.export_launcher_playsets(0).unwrap();
+
+        let connection = Connection::open(&path).unwrap();
+        // Registered mods are updated, new ones get the columns the launcher requires.
+        let mods: Vec<(String, Option<String>, String, String, String, i64)> = connection
+            .prepare(
+                "SELECT gameRegistryId, steamId, displayName, status, source, timeUpdated FROM mods \
+                 WHERE gameRegistryId LIKE 'mod/_.mod' ORDER BY gameRegistryId",
+            )
+            .unwrap()
+            .query_map([], |row| {
+                Ok((
+                    row.get(0)?,
+                    row.get(1)?,
+                    row.get(2)?,
+                    row.get(3)?,
+                    row.get(4)?,
+                    row.get(5)?,
+                ))
+            })
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap();
+        let ready = String::from("ready_to_play");
+        let local = String::from("local");
+        assert_eq!(
+            mods,
+            vec![
+                (
+                    String::from("mod/1.mod"),
+                    None,
+                    String::from("First"),
+                    ready.clone(),
+                    local.clone(),
+                    10
+                ),
+                // The steam id of mod 2 belongs to the copy subscribed through steam.
+                (
+                    String::from("mod/2.mod"),
+                    None,
+                    String::from("Second"),
+                    ready.clone(),
+                    local.clone(),
+                    20
+                ),
+                (
+                    String::from("mod/3.mod"),
+                    None,
+                    String::from("Third"),
+                    ready,
+                    local,
+                    30
+                ),
+            ]
+        );
+        let steam_copy: Option<String> = connection
+            .query_row("SELECT steamId FROM mods WHERE id = 'c'", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(steam_copy.as_deref(), Some("2"));
+
+        // The launcher only mod stays in front of the exported ones.
+        assert_eq!(
+            launcher_mods(&connection, "p1"),
+            vec![
+                (
+                    String::from("Launcher only"),
+                    Some(String::from("999")),
+                    false
+                ),
+                (String::from("Second"), None, true),
+                (String::from("First"), None, true),
+                (String::from("Third"), None, false),
+            ]
+        );
+        // Removed playsets are neither reused nor brought back.
+        let removed: (bool, usize) = connection
+            .query_row(
+                "SELECT isRemoved, (SELECT COUNT(*) FROM playsets_mods WHERE playsetId = 'p0') \
+                 FROM playsets WHERE id = 'p0'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(removed, (true, 0));
+
+        let playsets: Vec<(String, bool, String, bool, bool)> = connection
+            .prepare(
+                "SELECT name, isActive, loadOrder, isRemoved, createdOn IS NOT NULL FROM playsets \
+                 WHERE id != 'p0' ORDER BY name",
+            )
+            .unwrap()
+            .query_map([], |row| {
+                Ok((
+                    row.get(0)?,
+                    row.get(1)?,
+                    row.get(2)?,
+                    row.get(3)?,
+                    row.get(4)?,
+                ))
+            })
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap();
+        let custom = String::from("custom");
+        assert_eq!(
+            playsets,
+            vec![
+                (String::from("Launcher"), false, custom.clone(), false, true),
+                (String::from("Main"), true, custom.clone(), false, true),
+                (String::from("Other"), false, custom, false, true),
+            ]
+        );
+        drop(connection);
+
+        let mut imported = self::manager(&dir);
+        let names = imported.import_launcher_playsets(0).unwrap();
+        assert_eq!(names, vec!["Launcher", "Main", "Other"]);
+        let playsets = &imported.game(0).unwrap().playsets;
+        assert_eq!(playsets[1..], exported[..]);
+    }
+}
@@ -0,0 +1,263 @@
+//! Ordering the enabled mods of a game.
+//!
+//! The load order is the order of the game's list of enabled mods, which is also saved in the active playset.
+
+use std::{collections::HashMap, path::Path};
+
+use anyhow::{bail, Result};
+
+use super::ModManager;
+use crate::{dependencies, descriptor::Descriptor, enabled::mod_entry};
+
+impl ModManager {
+    /// Returns the enabled mods of the game at `game_selection` in load order.
+    ///
+    /// Each mod is listed with its entry in the game's list of enabled mods and its name.
+    pub fn load_order(&mut self, game_selection: usize) -> Result<Vec<(String, String)>> {
+        let entries = self.read_enabled_mods(game_selection)?;
+        let game = self.game(game_selection)?;
+        Ok(entries
+            .into_iter()
+            .map(|entry| {
+                let title = game
+                    .mods
+                    .iter()
+                    .find(|(key, _)| mod_entry(&game.path_mods, key) == entry)
+                    .map_or_else(|| entry.clone(), |(_, item_mod)| item_mod.title.clone());
+                (entry, title)
+            })
+            .collect())
+    }
+
+    /// Returns the position of the mod with the configuration key `key` in the load order of the game at `game_selection`.
+    pub fn load_order_position(&mut self, game_selection: usize, key: &str) -> Result<usize> {
+        let entries = self.read_enabled_mods(game_selection)?;
+        let game = self.game(game_selection)?;
+        let entry = mod_entry(&game.path_mods, key);
+        match entries.iter().position(|enabled| *enabled == entry) {
+            Some(position) => Ok(position),
+            None => bail!("Mod {} is not enabled for {}!", key, game.title),
+        }
+    }
+
+    /// Moves the mod at position `from` of the load order of the game at `game_selection` to position `to`.
+    pub fn move_in_load_order(
+        &mut self,
+        game_selection: usize,
+        from: usize,
+        to: usize,
+    ) -> Result<()> {
+        let mut entries = self.read_enabled_mods(game_selection)?;
+        if from >= entries.len() {
+            bail!("There is no enabled mod at position {}!", from + 1);
+        }
+        let entry = entries.remove(from);
+        entries.insert(to.min(entries.len()), entry);
+        self.write_enabled_mods(game_selection, &entries)
+    }
+
+    /// Makes the mod with the configuration key `key` load after the mod `after` when the load order is sorted.
+    pub fn add_load_rule(&mut self, game_selection: usize, key: &str, after: u64) -> Result<()> {
+        let game = self.game_mut(game_selection)?;
+        if !game.mods.contains_key(&after.to_string()) {
+            bail!("Mod {} is not installed for {}!", after, game.title);
+        }
+        if key == after.to_string() {
+            bail!("A mod can't load after itself!");
+        }
+        let title = game.title.clone();
+        match game.mods.get_mut(key) {
+            Some(item_mod) if !item_mod.load_after.contains(&after) => {
+                item_mod.load_after.push(after)
+            }
+            Some(_) => {}
+            None => bail!("Mod {} is not installed for {}!", key, title),
+        }
+        self.save_config()
+    }
+
+    /// Removes the rule that the mod with the configuration key `key` loads after the mod `after`.
+    pub fn remove_load_rule(&mut self, game_selection: usize, key: &str, after: u64) -> Result<()> {
+        let game = self.game_mut(game_selection)?;
+        let title = game.title.clone();
+        match game.mods.get_mut(key) {
+            Some(item_mod) => item_mod.load_after.retain(|&id| id != after),
+            None => bail!("Mod {} is not installed for {}!", key, title),
+        }
+        self.save_config()
+    }
+
+    /// Sorts the load order of the game at `game_selection` and writes it to the game's list of enabled mods.
+    ///
+    /// Mods load after the mods named as dependencies in their descriptor and after the mods of their load rules,
+    /// otherwise the order stays as it is. Returns the names of the mods of every cycle which had to be broken up.
+    pub fn sort_load_order(&mut self, game_selection: usize) -> Result<Vec<Vec<String>>> {
+        let order = self.load_order(game_selection)?;
+        let game = self.game(game_selection)?;
+        let descriptor = |key: &str| {
+            Descriptor::read(&Path::new(game.path_mods.trim()).join(format!("{}.mod", key))).ok()
+        };
+
+        let position = |entry: &str| order.iter().position(|(enabled, _)| enabled == entry);
+        let mut names = HashMap::new();
+        for key in game.mods.keys() {
+            if let Some(name) = descriptor(key).and_then(|descriptor| descriptor.name) {
+                names
+                    .entry(name)
+                    .or_insert_with(Vec::new)
+                    .push(mod_entry(&game.path_mods, key));
+            }
+        }
+
+        let mut after: HashMap<usize, Vec<usize>> = HashMap::new();
+        for (key, item_mod) in &game.mods {
+            let index = match position(&mod_entry(&game.path_mods, key)) {
+                Some(index) => index,
+                None => continue,
+            };
+            let mut loads_after: Vec<usize> = descriptor(key)
+                .map(|descriptor| descriptor.dependencies)
+                .unwrap_or_default()
+                .iter()
+                .filter_map(|name| names.get(name))
+                .flatten()
+                .filter_map(|entry| position(entry))
+                .collect();
+            loads_after.extend(
+                item_mod
+                    .load_after
+                    .iter()
+                    .filter_map(|id| position(&mod_entry(&game.path_mods, &id.to_string()))),
+            );
+            let _old = after.insert(index, loads_after);
+        }
+
+        let indices: Vec<usize> = (0..order.len()).collect();
+        let (sorted, cycles) = dependencies::sort_load_order(&indices, &after);
+        let entries: Vec<String> = sorted.iter().map(|&index| order[index].0.clone()).collect();
+        let cycles = cycles
+            .into_iter()
+            .map(|cycle| {
+                cycle
+                    .into_iter()
+                    .map(|index| order[index].1.clone())
+                    .collect()
+            })
+            .collect();
+
+        self.write_enabled_mods(game_selection, &entries)?;
+        Ok(cycles)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::{source::Source, Mod};
+
+    /// Installs mods with the id, name, descriptor dependencies and load rules of `mods` and enables `enabled` in that order.
+    fn manager(
+        dir: &TempDir,
+        mods: &[(u64, &str, &[&str], &[u64])],
+        enabled: &[u64],
+    ) -> ModManager {
+        let mut manager = ModManager::for_test(dir.path());
+        let game = manager.game_mut(0).unwrap();
+        for &(id, name, dependencies, load_after) in mods {
+            let descriptor = Descriptor {
+                name: Some(String::from(name)),
+                dependencies: dependencies
+                    .iter()
+                    .map(|name| String::from(*name))
+                    .collect(),
+                ..Descriptor::default()
+            };
+            fs::write(
+                Path::new(&game.path_mods).join(format!("{}.mod", id)),
+                descriptor.to_string(),
+            )
+            .unwrap();
+            let mut item_mod = Mod::new(id, String::from(name), 0, Source::Workshop);
+            item_mod.load_after = load_after.to_vec();
+            let _old = game.mods.insert(id.to_string(), item_mod);
+        }
+        let entries: Vec<String> = enabled
+            .iter()
+            .map(|id| mod_entry(&game.path_mods, &id.to_string()))
+            .collect();
+        manager.write_enabled_mods(0, &entries).unwrap();
+        manager
+    }
+
+    /// Returns the titles of the game's mods in load order.
+    fn titles(manager: &mut ModManager) -> Vec<String> {
+        manager
+            .load_order(0)
+            .unwrap()
+            .into_iter()
+            .map(|(_entry, title)| title)
+            .collect()
+    }
+
+    #[test]
+    fn dependencies_and_load_rules_are_loaded_first() {
+        let dir = TempDir::new().unwrap();
+        let mut manager = manager(
+            &dir,
+            &[
+                (1, "Core", &[], &[]),
+                (2, "Patch", &["Core"], &[]),
+                (3, "Interface", &["Disabled"], &[]),
+                (4, "Tweaks", &[], &[3]),
+                (5, "Disabled", &[], &[]),
+            ],
+            &[2, 4, 3, 1],
+        );
+
+        let cycles = manager.sort_load_order(0).unwrap();
+        assert!(cycles.is_empty());
+        assert_eq!(
+            titles(&mut manager),
+            ["Core", "Patch", "Interface", "Tweaks"]
+        );
+    }
+
+    #[test]
+    fn sorted_load_orders_stay_the_same() {
+        let dir = TempDir::new().unwrap();
+        let mut manager = manager(
+            &dir,
+            &[
+                (1, "Core", &[], &[]),
+                (2, "Patch", &["Core"], &[]),
+                (3, "Interface", &[], &[]),
+            ],
+            &[3, 1, 2],
+        );
+
+        let _cycles = manager.sort_load_order(0).unwrap();
+        assert_eq!(titles(&mut manager), ["Interface", "Core", "Patch"]);
+    }
+
+    #[test]
+    fn cycles_are_returned_as_names() {
+        let dir = TempDir::new().unwrap();
+        let mut manager = manager(
+            &dir,
+            &[
+                (1, "Core", &[], &[2]),
+                (2, "Patch", &["Core"], &[]),
+                (3, "Interface", &[], &[]),
+            ],
+            &[1, 2, 3],
+        );
+
+        let cycles = manager.sort_load_order(0).unwrap();
+        assert_eq!(cycles, vec![vec!["Core", "Patch"]]);
+        assert_eq!(titles(&mut manager), ["Patch", "Core", "Interface"]);
+    }
+}
@@ -0,0 +1,792 @@
+//! The `ModManager`, which ties configuration, filesystem and network functionality together.
+
+mod conflicts;
+mod enabled;
+mod launcher;
+mod load_order;
+mod playsets;
+mod sources;
+mod updates;
+mod versions;
+
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, bail, Result};
+
+use crate::{
+    backend::Download,
+    cancel::{is_cancelled, or_cancelled, CancellationToken, Downloads},
+    dependencies::InstallPlan,
+    descriptor::Descriptor,
+    discovery::{self, DiscoveredGame, KnownGame},
+    filesystem::{
+        archive_version, cache_dir, download_path, repository_path, staging_path, versions_path,
+        write_mod, CONFIG_FILE,
+    },
+    progress::{Printer, Progress},
+    remote::{ItemInfo, Remote},
+    source::Source,
+    Collection, Config, Game, Mod,
+};
+
+/// Holds the configuration and offers all operations on `Game`s and `Mod`s.
+///
+/// Every operation that changes the configuration also saves it to the config file.
+pub struct ModManager {
+    /// Holds the saved configuration of the program.
+    config: Config,
+    /// Path of the config file the configuration is saved to.
+    config_path: PathBuf,
+    /// Holds the current `reqwest::Client`.
+    remote: Remote,
+    /// Cancels the running operation.
+    cancellation: CancellationToken,
+    /// Tokens of the running downloads of an update.
+    downloads: Downloads,
+    /// Prints the messages of installations, above the progress bars while downloads are shown.
+    printer: Printer,
+    /// Folder the archived earlier versions of mods are kept in, `None` if the system has no cache folder.
+    cache_dir: Option<PathBuf>,
+}
+
+impl ModManager {
+    /// Instanciates a `ModManager` for tests with one game called `Stellaris`.
+    ///
+    /// The game's mod folder is `dir/Stellaris/mod`, the config file `dir/config.toml` and the cache folder `dir/cache`.
+    /// It is public for the tests of the front-end, which can't see the library's test code.
+    #[doc(hidden)]
+    pub fn for_test(dir: &Path) -> Self {
+        let path_mods = dir.join("Stellaris").join("mod");
+        fs::create_dir_all(&path_mods).expect("could not create the mod folder");
+        let config = Config {
+            retries: 0,
+            games: vec![Game {
+                title: "Stellaris".to_string(),
+                path_mods: path_mods.to_string_lossy().into_owned(),
+                app_id: None,
+                playset: None,
+                mods: HashMap::new(),
+                collections: Vec::new(),
+                playsets: Vec::new(),
+            }],
+            ..Config::default()
+        };
+        Self {
+            cache_dir: Some(dir.join("cache")),
+            ..Self::with_config(config, dir.join("config.toml"))
+        }
+    }
+}
+
+impl ModManager {
+    /// Instanciates a new `ModManager` with the configuration from the config file.
+    ///
+    /// Which mods are enabled is read from the games' lists of enabled mods, games whose list can't be read are
+    /// reported and keep the enabled mods of the configuration.
+    pub fn new() -> Result<Self> {
+        let mut manager = Self::with_config(Config::load(Path::new(CONFIG_FILE))?, CONFIG_FILE);
+        for game_selection in 0..manager.config.games.len() {
+            if let Err(error) = manager.read_enabled_mods(game_selection) {
+                println!(
+                    "Could not read the enabled mods of {}: {:#}",
+                    manager.config.games[game_selection].title, error
+                );
+            }
+        }
+        Ok(manager)
+    }
+
+    /// Instanciates a new `ModManager` with the given configuration, which is saved to `config_path`.
+    pub fn with_config(config: Config, config_path: impl Into<PathBuf>) -> Self {
+        Self {
+            remote: Remote::new(&config),
+            config,
+            config_path: config_path.into(),
+            cancellation: CancellationToken::new(),
+            downloads: Downloads::default(),
+            printer: Printer::default(),
+            cache_dir: cache_dir(),
+        }
+    }
+
+    /// Returns the folder the archived earlier versions of mods are kept in.
+    fn cache_dir(&self) -> Result<&Path> {
+        self.cache_dir
+            .as_deref()
+            .ok_or_else(|| anyhow!("Could not find the cache folder!"))
+    }
+
+    /// Saves the configuration to the config file.
+    fn save_config(&self) -> Result<()> {
+        self.config.update_config_file(&self.config_path)
+    }
+
+    /// Returns the token which cancels the running operation.
+    ///
+    /// Cancelled operations stop at the next safe point and fail with [`Cancelled`](crate::cancel::Cancelled),
+    /// mods which were completely installed until then stay in the configuration.
+    pub fn cancellation(&self) -> CancellationToken {
+        self.cancellation.clone()
+    }
+
+    /// Returns the handle which cancels single downloads of a running update.
+    ///
+    /// A cancelled download only skips its mod, the rest of the update goes on.
+    pub fn downloads(&self) -> Downloads {
+        self.downloads.clone()
+    }
+
+    /// Lets operations run again after the last one was cancelled.
+    pub fn reset_cancellation(&mut self) {
+        if self.cancellation.is_cancelled() {
+            self.cancellation = CancellationToken::new();
+        }
+    }
+
+    /// Returns the current configuration.
+    pub const fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Returns all configured `Game`s.
+    pub fn games(&self) -> &[Game] {
+        &self.config.games
+    }
+
+    /// Returns the `Game` at `game_selection`.
+    pub fn game(&self, game_selection: usize) -> Result<&Game> {
+        self.config
+            .games
+            .get(game_selection)
+            .ok_or_else(|| anyhow!("No game at position {}!", game_selection))
+    }
+
+    /// Returns the `Game` at `game_selection` mutably.
+    fn game_mut(&mut self, game_selection: usize) -> Result<&mut Game> {
+        self.config
+            .games
+            .get_mut(game_selection)
+            .ok_or_else(|| anyhow!("No game at position {}!", game_selection))
+    }
+
+    /// Returns the position of the `Game` called `title`, ignoring case.
+    pub fn find_game(&self, title: &str) -> Result<usize> {
+        self.config.find_game(title)
+    }
+
+    /// Adds a `Game` with the given title, mod folder and steam app id to the configuration.
+    pub fn add_game(
+        &mut self,
+        title: String,
+        path_mods: String,
+        app_id: Option<u64>,
+    ) -> Result<()> {
+        if title.len() > 30 {
+            bail!("Name is too long!");
+        }
+        if !Path::new(&path_mods).exists() {
+            bail!("The path {} does not exist!", path_mods);
+        }
+        if self.config.find_game(&title).is_ok() {
+            bail!("A game named {} is already configured!", title);
+        }
+
+        self.config.games.push(Game {
+            title,
+            path_mods,
+            app_id,
+            playset: None,
+            mods: HashMap::new(),
+            collections: Vec::new(),
+            playsets: Vec::new(),
+        });
+
+        self.save_config()
+    }
+
+    /// Detects installed games whose mod folder isn't configured yet.
+    pub fn discover_games(&self) -> Vec<DiscoveredGame> {
+        let configured: Vec<_> = self
+            .config
+            .games
+            .iter()
+            .map(|game| discovery::canonical(Path::new(game.path_mods.trim())))
+            .collect();
+
+        discovery::discover_games()
+            .into_iter()
+            .filter(|found| !configured.contains(&discovery::canonical(&found.path_mods)))
+            .collect()
+    }
+
+    /// Adds a detected game, creating its mod folder if it doesn't exist yet.
+    ///
+    /// If the name of the game is already taken, the short name and the location are used instead.
+    /// Returns the name the game was added with.
+    pub fn add_discovered_game(&mut self, found: &DiscoveredGame) -> Result<String> {
+        let title = if self.config.find_game(found.game.title).is_ok() {
+            format!("{} ({})", found.game.abbreviation, found.location)
+        } else {
+            found.game.title.to_string()
+        };
+        let path_mods = found
+            .path_mods
+            .to_str()
+            .ok_or_else(|| anyhow!("The path {:?} is not valid unicode!", found.path_mods))?
+            .to_string();
+
+        fs::create_dir_all(&found.path_mods)?;
+        self.add_game(title.clone(), path_mods, Some(found.game.app_id))?;
+        Ok(title)
+    }
+
+    /// Removes the `Game` at `game_selection` from the configuration. Installed mods are left untouched.
+    pub fn remove_game(&mut self, game_selection: usize) -> Result<()> {
+        let _old = self.game(game_selection)?;
+        let _old = self.config.games.remove(game_selection);
+        self.save_config()
+    }
+
+    /// Changes the name of the `Game` at `game_selection`.
+    pub fn rename_game(&mut self, game_selection: usize, title: String) -> Result<()> {
+        if title.len() > 30 {
+            bail!("Name is too long!");
+        }
+        if matches!(self.config.find_game(&title), Ok(found) if found != game_selection) {
+            bail!("A game named {} is already configured!", title);
+        }
+        self.game_mut(game_selection)?.title = title;
+        self.save_config()
+    }
+
+    /// Changes the mod folder of the `Game` at `game_selection`.
+    pub fn set_game_path(&mut self, game_selection: usize, path_mods: String) -> Result<()> {
+        if !Path::new(&path_mods).exists() {
+            bail!("The path {} does not exist!", path_mods);
+        }
+        self.game_mut(game_selection)?.path_mods = path_mods;
+        self.save_config()
+    }
+
+    /// Gets the metadata of a workshop item.
+    pub async fn get_item_info(&self, item_id: u64) -> Result<ItemInfo> {
+        or_cancelled(&self.cancellation, self.remote.get_item_info(item_id)).await
+    }
+
+    /// Gets the metadata of many workshop items at once. Items which aren't available are left out.
+    pub async fn get_items_info(&self, item_ids: &[u64]) -> Result<HashMap<u64, ItemInfo>> {
+        or_cancelled(&self.cancellation, self.remote.get_items_info(item_ids)).await
+    }
+
+    /// Checks that a workshop item belongs to the game at `game_selection`.
+    ///
+    /// Games without a known steam app id accept every item.
+    pub fn check_item(&self, game_selection: usize, item_info: &ItemInfo) -> Result<()> {
+        let game = self.game(game_selection)?;
+        match game.app_id() {
+            Some(app_id) if app_id != item_info.consumer_app_id => {
+                let item_game = KnownGame::by_app_id(item_info.consumer_app_id).map_or_else(
+                    || "another game".to_string(),
+                    |known| known.title.to_string(),
+                );
+                bail!(
+                    "{} is a mod for {} (app id {}), not for {} (app id {})!",
+                    item_info.title,
+                    item_game,
+                    item_info.consumer_app_id,
+                    game.title,
+                    app_id
+                )
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Downloads and installs a mod for the game at `game_selection` and saves it in the configuration.
+    ///
+    /// Use [`check_item`](Self::check_item) beforehand to make sure the mod belongs to the game.
+    pub async fn install_mod(
+        &mut self,
+        game_selection: usize,
+        item_id: u64,
+        item_info: ItemInfo,
+    ) -> Result<()> {
+        let directory = download_path(&self.game(game_selection)?.path_mods)?;
+        let progress = Progress::new(1);
+        let bar = progress.download(&item_info.title);
+        let file = or_cancelled(
+            &self.cancellation,
+            self.remote
+                .download_item(item_id, item_info.consumer_app_id, &bar, &directory),
+        )
+        .await?;
+        bar.finish("done");
+        drop(bar);
+        progress.finish();
+        println!("Download finished!");
+        let _descriptor = self.install_download(
+            game_selection,
+            Mod::new(
+                item_id,
+                item_info.title,
+                item_info.time_updated,
+                Source::Workshop,
+            ),
+            file,
+        )?;
+        println!("Mod installed!.");
+        Ok(())
+    }
+
+    /// Installs a downloaded mod for the game at `game_selection` and saves it in the configuration.
+    ///
+    /// Mods which are already installed keep their settings and only get the new version and source.
+    /// Messages are printed with the manager's `Printer`. Returns the descriptor written to the `.mod` file.
+    fn install_download(
+        &mut self,
+        game_selection: usize,
+        item: Mod,
+        file: Download,
+    ) -> Result<Descriptor> {
+        let game = self.game(game_selection)?;
+        let path_mods = game.path_mods.clone();
+
+        self.printer
+            .println(format!("### Installing {} ###", item.title));
+        if let Some(installed) = game.mods.get(&item.id.to_string()) {
+            archive_version(
+                self.cache_dir()?,
+                &path_mods,
+                item.id,
+                installed.time_updated,
+                self.config.kept_versions,
+            )
+            .map_err(|error| {
+                anyhow!(
+                    "Could not archive the installed version of {}: {}",
+                    installed.title,
+                    error
+                )
+            })?;
+        }
+        let descriptor = write_mod(
+            item.id,
+            item.title.clone(),
+            &item.source,
+            file,
+            &path_mods,
+            &self.printer,
+        )?;
+
+        self.printer.println("Updating config file.");
+        let key = item.id.to_string();
+        let game = self.game_mut(game_selection)?;
+        if let Some(installed) = game.mods.get_mut(&key) {
+            installed.time_updated = item.time_updated;
+            installed.version = item.version;
+            installed.sha256 = item.sha256;
+            installed.source = item.source;
+            self.save_config()?;
+        } else {
+            let title = item.title.clone();
+            let _old = game.mods.insert(key.clone(), item);
+            self.save_config()?;
+            if let Err(error) = self.update_enabled_mods(game_selection, Some((&key, true))) {
+                self.printer.println(format!(
+                    "Could not enable {} in the game: {:#}",
+                    title, error
+                ));
+            }
+        }
+        Ok(descriptor)
+    }
+
+    /// Gets the metadata of a workshop item and all items it requires and orders them for installation.
+    pub async fn plan_install(&mut self, item_id: u64) -> Result<InstallPlan> {
+        let infos = or_cancelled(&self.cancellation, self.remote.get_item_tree(item_id)).await?;
+        if !infos.contains_key(&item_id) {
+            bail!("Could not get the mod info of {}!", item_id);
+        }
+        Ok(InstallPlan::new(item_id, infos))
+    }
+
+    /// Installs all mods of an `InstallPlan` for the game at `game_selection`.
+    ///
+    /// Dependencies which are already installed and up to date are not downloaded again, pinned mods including the
+    /// requested one stay at their version. Mods installed only because of the plan's requested mod are marked as dependencies.
+    pub async fn install_plan(&mut self, game_selection: usize, plan: &InstallPlan) -> Result<()> {
+        for (item_id, item_info) in &plan.items {
+            let game = self.game(game_selection)?;
+            let installed_mod = game.mods.get(&item_id.to_string());
+            let installed = installed_mod
+                .map(|item_mod| item_mod.pinned || item_mod.time_updated >= item_info.time_updated);
+
+            if installed_mod.is_some_and(|item_mod| item_mod.pinned) {
+                println!(
+                    "Mod {} for {} is pinned, it is not reinstalled.",
+                    item_info.title, game.title
+                );
+            } else if *item_id != plan.root && installed == Some(true) {
+                println!("Dependency {} is already installed.", item_info.title);
+            } else {
+                self.install_mod(game_selection, *item_id, item_info.clone())
+                    .await?;
+            }
+
+            let required_by = plan.required_by(*item_id);
+            let item_mod = self
+                .game_mut(game_selection)?
+                .mods
+                .get_mut(&item_id.to_string())
+                .expect("installed mod missing");
+            if *item_id == plan.root {
+                item_mod.dependency = false;
+            } else if installed.is_none() {
+                item_mod.dependency = true;
+            }
+            for requiring in required_by {
+                if !item_mod.required_by.contains(&requiring) {
+                    item_mod.required_by.push(requiring);
+                }
+            }
+        }
+        self.save_config()?;
+
+        let root_key = plan.root.to_string();
+        for name in self.missing_dependencies(game_selection, &root_key)? {
+            println!(
+                "Warning: {} depends on {}, which is not installed!",
+                self.game(game_selection)?.mods[&root_key].title,
+                name
+            );
+        }
+        Ok(())
+    }
+
+    /// Gets the metadata of a workshop collection and of all its members.
+    pub async fn get_collection(
+        &mut self,
+        collection_id: u64,
+    ) -> Result<(ItemInfo, Vec<(u64, ItemInfo)>)> {
+        let collection_info = self.get_item_info(collection_id).await?;
+        let members = or_cancelled(
+            &self.cancellation,
+            self.remote.get_collection_details(collection_id),
+        )
+        .await?;
+
+        let mut infos = self.get_items_info(&members).await?;
+        let member_infos = members
+            .into_iter()
+            .filter_map(|member| {
+                let info = infos.remove(&member);
+                if info.is_none() {
+                    println!("Skipping workshop item {}, it is not available.", member);
+                }
+                info.map(|info| (member, info))
+            })
+            .collect();
+        Ok((collection_info, member_infos))
+    }
+
+    /// Installs the `selected` members of a collection with their dependencies for the game at `game_selection`.
+    ///
+    /// The collection is saved with the game so [`sync_collections`](Self::sync_collections) can install members
+    /// added later and remove members which were dropped from it.
+    pub async fn install_collection(
+        &mut self,
+        game_selection: usize,
+        collection_id: u64,
+        title: String,
+        members: Vec<u64>,
+        selected: &[u64],
+    ) -> Result<()> {
+        for &member in selected {
+            self.install_collection_member(game_selection, collection_id, member)
+                .await?;
+        }
+
+        let excluded = members
+            .iter()
+            .filter(|member| !selected.contains(member))
+            .copied()
+            .collect();
+        let game = self.game_mut(game_selection)?;
+        game.collections
+            .retain(|collection| collection.id != collection_id);
+        game.collections.push(Collection {
+            title,
+            id: collection_id,
+            members,
+            excluded,
+        });
+
+        self.save_config()
+    }
+
+    /// Installs a single member of a collection unless it is already installed.
+    async fn install_collection_member(
+        &mut self,
+        game_selection: usize,
+        collection_id: u64,
+        member: u64,
+    ) -> Result<()> {
+        if let Some(item_mod) = self.game(game_selection)?.mods.get(&member.to_string()) {
+            println!("{} is already installed.", item_mod.title);
+            return Ok(());
+        }
+
+        let plan = self.plan_install(member).await?;
+        self.install_plan(game_selection, &plan).await?;
+
+        if let Some(item_mod) = self
+            .game_mut(game_selection)?
+            .mods
+            .get_mut(&member.to_string())
+        {
+            item_mod.collection = Some(collection_id);
+        }
+        self.save_config()
+    }
+
+    /// Brings the game's collections up to date.
+    ///
+    /// New members are installed, members dropped from a collection are removed if they were installed
+    /// from it and no other mod requires them. A collection or member which fails doesn't stop the others,
+    /// the failures are returned with the name of what failed. Members which couldn't be installed are
+    /// tried again the next time.
+    pub async fn sync_collections(
+        &mut self,
+        game_selection: usize,
+    ) -> Result<Vec<(String, anyhow::Error)>> {
+        let mut failures = Vec::new();
+        for collection in self.game(game_selection)?.collections.clone() {
+            let members = match or_cancelled(
+                &self.cancellation,
+                self.remote.get_collection_details(collection.id),
+            )
+            .await
+            {
+                Ok(members) => members,
+                Err(error) if is_cancelled(&error) => return Err(error),
+                Err(error) => {
+                    failures.push((format!("Collection {}", collection.title), error));
+                    continue;
+                }
+            };
+
+            let mut failed = Vec::new();
+            for &added in members
+                .iter()
+                .filter(|member| !collection.members.contains(member))
+            {
+                println!("New mod {} in collection {}.", added, collection.title);
+                if let Err(error) = self
+                    .install_collection_member(game_selection, collection.id, added)
+                    .await
+                {
+                    if is_cancelled(&error) {
+                        return Err(error);
+                    }
+                    failures.push((
+                        format!("Mod {} of collection {}", added, collection.title),
+                        error,
+                    ));
+                    failed.push(added);
+                }
+            }
+
+            for dropped in collection
+                .members
+                .iter()
+                .filter(|member| !members.contains(member))
+            {
+                let key = dropped.to_string();
+                let removable = match self.game(game_selection)?.mods.get(&key) {
+                    Some(item_mod) if item_mod.collection == Some(collection.id) => {
+                        if item_mod.required_by.is_empty() {
+                            true
+                        } else {
+                            println!(
+                                "{} was removed from collection {} but is still required by other mods.",
+                                item_mod.title, collection.title
+                            );
+                            false
+                        }
+                    }
+                    _ => false,
+                };
+                if removable {
+                    println!(
+                        "Removing {} because it was removed from collection {}.",
+                        key, collection.title
+                    );
+                    if let Err(error) = self.remove_mod(game_selection, &key) {
+                        failures.push((
+                            format!("Removing {} of collection {}", key, collection.title),
+                            error,
+                        ));
+                    }
+                }
+            }
+
+            if let Some(saved) = self
+                .game_mut(game_selection)?
+                .collections
+                .iter_mut()
+                .find(|saved| saved.id == collection.id)
+            {
+                saved.excluded.retain(|member| members.contains(member));
+                saved.members = members
+                    .into_iter()
+                    .filter(|member| !failed.contains(member))
+                    .collect();
+            }
+        }
+        self.save_config()?;
+        Ok(failures)
+    }
+
+    /// Returns the names of the dependencies listed in the descriptor of the mod `key`
+    /// that don't match the name of any installed mod.
+    pub fn missing_dependencies(&self, game_selection: usize, key: &str) -> Result<Vec<String>> {
+        let game = self.game(game_selection)?;
+        let descriptor = |key: &str| {
+            Descriptor::read(&Path::new(game.path_mods.trim()).join(format!("{}.mod", key))).ok()
+        };
+
+        let installed: Vec<String> = game
+            .mods
+            .keys()
+            .filter_map(|key| descriptor(key))
+            .filter_map(|descriptor| descriptor.name)
+            .collect();
+
+        Ok(descriptor(key)
+            .map(|descriptor| descriptor.dependencies)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|name| !installed.contains(name))
+            .collect())
+    }
+
+    /// Removes the mod with the configuration key `key` from disk, from the game at `game_selection` and from its list of enabled mods.
+    pub fn remove_mod(&mut self, game_selection: usize, key: &str) -> Result<()> {
+        let cache_dir = self.cache_dir()?.to_path_buf();
+        let game = self.game_mut(game_selection)?;
+
+        if !game.mods.contains_key(key) {
+            bail!("Mod {} is not installed for {}!", key, game.title);
+        }
+
+        let install_path = format!("{}/{}", game.path_mods.trim(), key);
+        let mod_file_path = format!("{}/{}.mod", game.path_mods.trim(), key);
+        if Path::new(&install_path).exists() {
+            fs::remove_dir_all(install_path)?;
+        }
+        if Path::new(&mod_file_path).exists() {
+            fs::remove_file(mod_file_path)?;
+        }
+        if let Ok(item_id) = key.parse() {
+            for folder in [
+                repository_path(&game.path_mods, item_id),
+                staging_path(&game.path_mods, item_id),
+                versions_path(&cache_dir, &game.path_mods, item_id),
+            ] {
+                if folder.exists() {
+                    fs::remove_dir_all(folder)?;
+                }
+            }
+        }
+        let _old = game.mods.remove(key);
+        for playset in &mut game.playsets {
+            playset
+                .mods
+                .retain(|playset_mod| playset_mod.id.to_string() != key);
+        }
+
+        for item_mod in game.mods.values_mut() {
+            item_mod.required_by.retain(|id| id.to_string() != key);
+            item_mod.load_after.retain(|id| id.to_string() != key);
+            if item_mod.dependency && item_mod.required_by.is_empty() {
+                println!(
+                    "{} was installed as a dependency and is no longer required.",
+                    item_mod.title
+                );
+            }
+        }
+
+        self.save_config()?;
+        self.update_enabled_mods(game_selection, None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::remote::ItemChild;
+
+    #[tokio::test]
+    async fn pinned_mods_are_not_reinstalled_by_install_plans() {
+        let dir = TempDir::new().unwrap();
+        let mut manager = ModManager::for_test(dir.path());
+        let game = manager.game_mut(0).unwrap();
+        let mut pinned = Mod::new(1, String::from("Mod"), 5, Source::Workshop);
+        pinned.pinned = true;
+        let _old = game.mods.insert(String::from("1"), pinned);
+        let _old = game.mods.insert(
+            String::from("2"),
+            Mod::new(2, String::from("Core"), 10, Source::Workshop),
+        );
+        let info = |title: &str, time_updated: u64, children: Vec<ItemChild>| ItemInfo {
+            title: String::from(title),
+            time_updated,
+            consumer_app_id: 281990,
+            children,
+        };
+        let infos: HashMap<u64, ItemInfo> = vec![
+            (
+                1,
+                info(
+                    "Mod",
+                    20,
+                    vec![ItemChild {
+                        publishedfileid: String::from("2"),
+                    }],
+                ),
+            ),
+            (2, info("Core", 10, Vec::new())),
+        ]
+        .into_iter()
+        .collect();
+
+        manager
+            .install_plan(0, &InstallPlan::new(1, infos))
+            .await
+            .unwrap();
+        let mods = &manager.game(0).unwrap().mods;
+        assert_eq!(mods["1"].time_updated, 5);
+        assert_eq!(mods["2"].required_by, vec![1]);
+    }
+
+    #[test]
+    fn games_are_not_renamed_to_the_title_of_another_game() {
+        let dir = TempDir::new().unwrap();
+        let mut manager = ModManager::for_test(dir.path());
+        let path_mods = manager.game(0).unwrap().path_mods.clone();
+        manager
+            .add_game(String::from("Hearts of Iron IV"), path_mods, None)
+            .unwrap();
+
+        assert!(manager.rename_game(1, String::from("stellaris")).is_err());
+        assert_eq!(manager.game(1).unwrap().title, "Hearts of Iron IV");
+        manager.rename_game(0, String::from("stellaris")).unwrap();
+        assert_eq!(manager.game(0).unwrap().title, "stellaris");
+    }
+}
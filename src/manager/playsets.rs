@@ -0,0 +1,364 @@
+//! Named sets of mods which are enabled together.
+
+use std::collections::HashMap;
+
+use anyhow::{anyhow, bail, Result};
+
+use super::ModManager;
+use crate::{
+    enabled::{entry_exists, mod_entry},
+    Game, Playset, PlaysetMod,
+};
+
+/// Returns the position of the playset called `name` of `game`, ignoring case.
+fn find_playset(game: &Game, name: &str) -> Result<usize> {
+    game.playsets
+        .iter()
+        .position(|playset| playset.name.eq_ignore_ascii_case(name.trim()))
+        .ok_or_else(|| anyhow!("{} has no playset named {}!", game.title, name.trim()))
+}
+
+/// Checks that `name` can be used for a new playset of `game`.
+fn check_playset_name(game: &Game, name: &str) -> Result<()> {
+    if name.trim().is_empty() {
+        bail!("The name of a playset can't be empty!");
+    }
+    if name.len() > 30 {
+        bail!("Name is too long!");
+    }
+    if find_playset(game, name).is_ok() {
+        bail!(
+            "{} already has a playset named {}!",
+            game.title,
+            name.trim()
+        );
+    }
+    Ok(())
+}
+
+impl ModManager {
+    /// Saves the enabled mods `entries` of the game at `game_selection` in its active playset.
+    ///
+    /// Enabled mods are ordered like in `entries`, disabled ones follow in their previous order.
+    pub(super) fn capture_playset(
+        &mut self,
+        game_selection: usize,
+        entries: &[String],
+    ) -> Result<()> {
+        let game = self.game_mut(game_selection)?;
+        let index = match game
+            .playset
+            .as_deref()
+            .and_then(|name| find_playset(game, name).ok())
+        {
+            Some(index) => index,
+            None => return Ok(()),
+        };
+
+        let ids: HashMap<String, u64> = game
+            .mods
+            .values()
+            .map(|item_mod| {
+                (
+                    mod_entry(&game.path_mods, &item_mod.id.to_string()),
+                    item_mod.id,
+                )
+            })
+            .collect();
+        let mut mods: Vec<PlaysetMod> = entries
+            .iter()
+            .filter_map(|entry| ids.get(entry))
+            .map(|&id| PlaysetMod { id, enabled: true })
+            .collect();
+
+        let mut others: Vec<_> = game.mods.values().collect();
+        others.sort_by_key(|item_mod| item_mod.title.clone());
+        let previous = game.playsets[index]
+            .mods
+            .iter()
+            .map(|playset_mod| playset_mod.id);
+        for id in previous.chain(others.into_iter().map(|item_mod| item_mod.id)) {
+            if game.mods.contains_key(&id.to_string())
+                && !mods.iter().any(|playset_mod| playset_mod.id == id)
+            {
+                mods.push(PlaysetMod { id, enabled: false });
+            }
+        }
+
+        game.playsets[index].mods = mods;
+        Ok(())
+    }
+
+    /// Creates a playset called `name` with the currently enabled mods of the game at `game_selection` and makes it the active one.
+    pub fn create_playset(&mut self, game_selection: usize, name: String) -> Result<()> {
+        check_playset_name(self.game(game_selection)?, &name)?;
+        let entries = self.read_enabled_mods(game_selection)?;
+        self.capture_playset(game_selection, &entries)?;
+
+        let game = self.game_mut(game_selection)?;
+        let name = name.trim().to_string();
+        game.playsets.push(Playset {
+            name: name.clone(),
+            mods: Vec::new(),
+        });
+        game.playset = Some(name);
+        self.capture_playset(game_selection, &entries)?;
+        self.save_config()
+    }
+
+    /// Copies the playset called `source` of the game at `game_selection` into a new playset called `name`.
+    pub fn clone_playset(
+        &mut self,
+        game_selection: usize,
+        source: &str,
+        name: String,
+    ) -> Result<()> {
+        let game = self.game(game_selection)?;
+        let index = find_playset(game, source)?;
+        check_playset_name(game, &name)?;
+        let entries = self.read_enabled_mods(game_selection)?;
+        self.capture_playset(game_selection, &entries)?;
+
+        let game = self.game_mut(game_selection)?;
+        let mods = game.playsets[index].mods.clone();
+        game.playsets.push(Playset {
+            name: name.trim().to_string(),
+            mods,
+        });
+        self.save_config()
+    }
+
+    /// Makes the playset called `name` the active one and enables exactly its mods in the game at `game_selection`.
+    ///
+    /// The enabled mods are saved in the previously active playset first.
+    /// Mods in the game's list of enabled mods which aren't managed here stay enabled and are loaded before the
+    /// playset's mods, entries whose `.mod` file is gone are dropped.
+    pub fn switch_playset(&mut self, game_selection: usize, name: &str) -> Result<()> {
+        let index = find_playset(self.game(game_selection)?, name)?;
+        let current = self.read_enabled_mods(game_selection)?;
+        self.capture_playset(game_selection, &current)?;
+
+        let game = self.game_mut(game_selection)?;
+        let playset = game.playsets[index].clone();
+        let path_mods = game.path_mods.clone();
+        let mut entries: Vec<String> = current
+            .into_iter()
+            .filter(|entry| {
+                !game
+                    .mods
+                    .keys()
+                    .any(|key| mod_entry(&path_mods, key) == *entry)
+                    && entry_exists(&path_mods, entry)
+            })
+            .collect();
+        for playset_mod in playset
+            .mods
+            .iter()
+            .filter(|playset_mod| playset_mod.enabled)
+        {
+            let key = playset_mod.id.to_string();
+            let entry = mod_entry(&path_mods, &key);
+            if game.mods.contains_key(&key) && !entries.contains(&entry) {
+                entries.push(entry);
+            }
+        }
+        for (key, item_mod) in &mut game.mods {
+            item_mod.enabled = entries.contains(&mod_entry(&path_mods, key));
+        }
+
+        game.playset = Some(playset.name);
+        self.write_enabled_mods(game_selection, &entries)
+    }
+
+    /// Renames the playset called `name` of the game at `game_selection` to `new_name`. An active playset stays active.
+    pub fn rename_playset(
+        &mut self,
+        game_selection: usize,
+        name: &str,
+        new_name: String,
+    ) -> Result<()> {
+        let game = self.game_mut(game_selection)?;
+        let index = find_playset(game, name)?;
+        if !game.playsets[index]
+            .name
+            .eq_ignore_ascii_case(new_name.trim())
+        {
+            check_playset_name(game, &new_name)?;
+        }
+
+        let new_name = new_name.trim().to_string();
+        if game.playset.as_deref() == Some(game.playsets[index].name.as_str()) {
+            game.playset = Some(new_name.clone());
+        }
+        game.playsets[index].name = new_name;
+        self.save_config()
+    }
+
+    /// Deletes the playset called `name` of the game at `game_selection`. The enabled mods stay as they are.
+    pub fn delete_playset(&mut self, game_selection: usize, name: &str) -> Result<()> {
+        let game = self.game_mut(game_selection)?;
+        let index = find_playset(game, name)?;
+        let playset = game.playsets.remove(index);
+        if game.playset.as_deref() == Some(playset.name.as_str()) {
+            game.playset = None;
+        }
+        self.save_config()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::Path};
+
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::{enabled::user_dir, source::Source, Config, Mod};
+
+    /// Instantiates a manager whose game has the mods 1 `Alpha`, 2 `Beta` and 3 `Gamma`, none of them enabled.
+    fn manager(dir: &TempDir) -> ModManager {
+        let mut manager = ModManager::for_test(dir.path());
+        let game = manager.game_mut(0).unwrap();
+        for &(id, title) in &[(1, "Alpha"), (2, "Beta"), (3, "Gamma")] {
+            let _old = game.mods.insert(
+                id.to_string(),
+                Mod::new(id, String::from(title), 10, Source::Workshop),
+            );
+        }
+        manager
+    }
+
+    /// Writes `entries` into the game's `dlc_load.json`, like the launcher does.
+    fn write_dlc_load(manager: &ModManager, entries: &[&str]) {
+        let path = user_dir(&manager.game(0).unwrap().path_mods).join("dlc_load.json");
+        let dlc_load = json!({ "enabled_mods": entries, "disabled_dlcs": [] });
+        fs::write(path, dlc_load.to_string()).unwrap();
+    }
+
+    /// Returns the game's list of enabled mods.
+    fn enabled(manager: &mut ModManager) -> Vec<String> {
+        manager.read_enabled_mods(0).unwrap()
+    }
+
+    /// Returns the ids and enabled flags of the mods of the playset called `name`.
+    fn playset(manager: &ModManager, name: &str) -> Vec<(u64, bool)> {
+        let game = manager.game(0).unwrap();
+        game.playsets[find_playset(game, name).unwrap()]
+            .mods
+            .iter()
+            .map(|playset_mod| (playset_mod.id, playset_mod.enabled))
+            .collect()
+    }
+
+    #[test]
+    fn playsets_are_created_from_the_enabled_mods() {
+        let dir = TempDir::new().unwrap();
+        let mut manager = manager(&dir);
+        write_dlc_load(&manager, &["mod/2.mod", "mod/1.mod"]);
+
+        manager
+            .create_playset(0, String::from(" Campaign "))
+            .unwrap();
+        assert_eq!(
+            manager.game(0).unwrap().playset.as_deref(),
+            Some("Campaign")
+        );
+        assert_eq!(
+            playset(&manager, "campaign"),
+            vec![(2, true), (1, true), (3, false)]
+        );
+        assert!(manager.create_playset(0, String::from("CAMPAIGN")).is_err());
+        assert!(manager.create_playset(0, String::from(" ")).is_err());
+        assert_eq!(
+            Config::load(&dir.path().join("config.toml")).unwrap().games[0].playsets,
+            manager.game(0).unwrap().playsets
+        );
+    }
+
+    #[test]
+    fn switching_playsets_puts_unmanaged_entries_first() {
+        let dir = TempDir::new().unwrap();
+        let mut manager = manager(&dir);
+        let path_mods = manager.game(0).unwrap().path_mods.clone();
+        fs::write(Path::new(&path_mods).join("other.mod"), "name=\"Other\"").unwrap();
+        write_dlc_load(&manager, &["mod/1.mod", "mod/other.mod", "mod/gone.mod"]);
+        manager.create_playset(0, String::from("First")).unwrap();
+        manager.create_playset(0, String::from("Second")).unwrap();
+        manager.set_mod_enabled(0, "1", false).unwrap();
+        manager.set_mod_enabled(0, "3", true).unwrap();
+
+        manager.switch_playset(0, "First").unwrap();
+        assert_eq!(enabled(&mut manager), vec!["mod/other.mod", "mod/1.mod"]);
+        assert_eq!(manager.game(0).unwrap().playset.as_deref(), Some("First"));
+        let mods = &manager.game(0).unwrap().mods;
+        assert!(mods["1"].enabled && !mods["3"].enabled);
+
+        manager.switch_playset(0, "second").unwrap();
+        assert_eq!(enabled(&mut manager), vec!["mod/other.mod", "mod/3.mod"]);
+        assert_eq!(
+            playset(&manager, "Second"),
+            vec![(3, true), (1, false), (2, false)]
+        );
+        assert!(manager.switch_playset(0, "Third").is_err());
+    }
+
+    #[test]
+    fn playsets_are_renamed() {
+        let dir = TempDir::new().unwrap();
+        let mut manager = manager(&dir);
+        manager.create_playset(0, String::from("First")).unwrap();
+        manager.create_playset(0, String::from("Second")).unwrap();
+
+        manager
+            .rename_playset(0, "second", String::from("Campaign"))
+            .unwrap();
+        assert_eq!(
+            manager.game(0).unwrap().playset.as_deref(),
+            Some("Campaign")
+        );
+        assert!(manager
+            .rename_playset(0, "Campaign", String::from("first"))
+            .is_err());
+        assert!(manager
+            .rename_playset(0, "Third", String::from("Fourth"))
+            .is_err());
+        manager
+            .rename_playset(0, "first", String::from("FIRST"))
+            .unwrap();
+
+        let game = &Config::load(&dir.path().join("config.toml")).unwrap().games[0];
+        let names: Vec<&str> = game
+            .playsets
+            .iter()
+            .map(|playset| playset.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["FIRST", "Campaign"]);
+        assert_eq!(game.playset.as_deref(), Some("Campaign"));
+    }
+
+    #[test]
+    fn external_edits_of_the_enabled_mods_are_captured() {
+        let dir = TempDir::new().unwrap();
+        let mut manager = manager(&dir);
+        write_dlc_load(&manager, &["mod/1.mod"]);
+        manager.create_playset(0, String::from("Campaign")).unwrap();
+
+        // The launcher enabled another mod before the one of the playset.
+        write_dlc_load(&manager, &["mod/3.mod", "mod/1.mod"]);
+        manager.create_playset(0, String::from("Other")).unwrap();
+        assert_eq!(
+            playset(&manager, "Campaign"),
+            vec![(3, true), (1, true), (2, false)]
+        );
+
+        // Disabled mods keep their previous order.
+        write_dlc_load(&manager, &[]);
+        manager.switch_playset(0, "Campaign").unwrap();
+        assert_eq!(enabled(&mut manager), vec!["mod/3.mod", "mod/1.mod"]);
+        assert_eq!(
+            playset(&manager, "Other"),
+            vec![(3, false), (1, false), (2, false)]
+        );
+    }
+}
@@ -0,0 +1,848 @@
+//! Installing and updating mods which aren't from the steam workshop.
+
+use std::{
+    fs::{self, File},
+    io,
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{anyhow, bail, Result};
+use sha2::{Digest, Sha256};
+
+use super::ModManager;
+use crate::{
+    backend::Download,
+    cancel::{is_cancelled, or_cancelled},
+    descriptor::Descriptor,
+    discovery,
+    filesystem::{download_path, modified_time, read_local, repository_path},
+    git,
+    progress::Progress,
+    remote::UrlFile,
+    source::{self, Source},
+    Mod,
+};
+
+/// Returns the SHA-256 hash of the file at `path` in lowercase hex.
+fn sha256_file(path: &Path) -> Result<String> {
+    let mut hasher = Sha256::new();
+    let _bytes = io::copy(&mut File::open(path)?, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Fails if the SHA-256 hash of the file at `path` isn't `expected`.
+fn verify_sha256(path: &Path, expected: &str) -> Result<()> {
+    let hash = sha256_file(path)?;
+    if !hash.eq_ignore_ascii_case(expected.trim()) {
+        bail!(
+            "The SHA-256 hash of the download is {}, but {} was expected!",
+            hash,
+            expected.trim()
+        );
+    }
+    Ok(())
+}
+
+/// Returns the current time in unix time.
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_secs())
+}
+
+impl ModManager {
+    /// Installs a `.zip` archive or folder from this computer for the game at `game_selection`.
+    ///
+    /// The mod is named `title` or otherwise after its descriptor. Returns the name of the mod.
+    pub fn install_local(
+        &mut self,
+        game_selection: usize,
+        path: &str,
+        title: Option<String>,
+    ) -> Result<String> {
+        let source_path = discovery::canonical(Path::new(path.trim()));
+        let download = read_local(&source_path)?;
+        let location = source_path.to_string_lossy().into_owned();
+        let item_id = source::derived_id(&location);
+        let file_name = source_path.file_stem().map_or_else(
+            || location.clone(),
+            |name| name.to_string_lossy().into_owned(),
+        );
+
+        let descriptor = self.install_download(
+            game_selection,
+            Mod::new(
+                item_id,
+                title.clone().unwrap_or(file_name),
+                modified_time(&source_path)?,
+                Source::Local { path: location },
+            ),
+            download,
+        )?;
+        self.name_mod(game_selection, item_id, title, descriptor)
+    }
+
+    /// Clones a git repository and installs the mod in it for the game at `game_selection`.
+    ///
+    /// `reference` is the branch or tag to follow and `subdir` the folder of the mod inside the repository.
+    /// The mod is named `title` or otherwise after its descriptor. Returns the name of the mod.
+    pub async fn install_git(
+        &mut self,
+        game_selection: usize,
+        url: &str,
+        reference: Option<String>,
+        subdir: Option<String>,
+        title: Option<String>,
+    ) -> Result<String> {
+        let url = url.trim().to_string();
+        let item_id = source::derived_id(&format!(
+            "{} {} {}",
+            url,
+            reference.as_deref().unwrap_or_default(),
+            subdir.as_deref().unwrap_or_default()
+        ));
+        let repository = repository_path(&self.game(game_selection)?.path_mods, item_id);
+        // An installed mod keeps its repository until the new clone succeeded.
+        let clone = repository.with_extension("clone");
+        if clone.exists() {
+            fs::remove_dir_all(&clone)?;
+        }
+
+        println!("Cloning {}.", url);
+        self.clone_repository(&url, reference.as_deref(), &clone)
+            .await?;
+        if repository.exists() {
+            let old = repository.with_extension("old");
+            fs::rename(&repository, &old)?;
+            fs::rename(&clone, &repository)?;
+            fs::remove_dir_all(&old)?;
+        } else {
+            fs::rename(&clone, &repository)?;
+        }
+        let file_name = url
+            .trim_end_matches('/')
+            .trim_end_matches(".git")
+            .rsplit('/')
+            .next()
+            .unwrap_or(&url)
+            .to_string();
+
+        let source = Source::Git {
+            url,
+            reference,
+            subdir,
+        };
+        let descriptor = self
+            .install_repository(
+                game_selection,
+                item_id,
+                title.clone().unwrap_or(file_name),
+                source,
+            )
+            .await?;
+        self.name_mod(game_selection, item_id, title, descriptor)
+    }
+
+    /// Downloads a `.zip` archive from `url` and installs it for the game at `game_selection`.
+    ///
+    /// If `sha256` is set, the archive has to have that hash.
+    /// The mod is named `title` or otherwise after its descriptor. Returns the name of the mod.
+    pub async fn install_url(
+        &mut self,
+        game_selection: usize,
+        url: &str,
+        sha256: Option<String>,
+        title: Option<String>,
+    ) -> Result<String> {
+        let url = url.trim().to_string();
+        let item_id = source::derived_id(&url);
+
+        let UrlFile { file, version } = self
+            .fetch_url(game_selection, &url, None, title.as_deref().unwrap_or(&url))
+            .await?
+            .ok_or_else(|| anyhow!("{} sent no file!", url))?;
+        if let Some(sha256) = &sha256 {
+            verify_sha256(file.path(), sha256)?;
+        }
+        let hash = sha256_file(file.path())?;
+        let file_name = url
+            .split(['?', '#'])
+            .next()
+            .and_then(|path| path.trim_end_matches('/').rsplit('/').next())
+            .map_or_else(
+                || url.clone(),
+                |name| name.trim_end_matches(".zip").to_string(),
+            );
+
+        let mut item = Mod::new(
+            item_id,
+            title.clone().unwrap_or(file_name),
+            now(),
+            Source::Url { url, sha256 },
+        );
+        item.version = version;
+        item.sha256 = Some(hash);
+        let descriptor = self.install_download(game_selection, item, Download::Archive(file))?;
+        self.name_mod(game_selection, item_id, title, descriptor)
+    }
+
+    /// Downloads the file at `url` for the game at `game_selection` unless it didn't change since the download with `version`,
+    /// showing a progress bar for `title`.
+    async fn fetch_url(
+        &self,
+        game_selection: usize,
+        url: &str,
+        version: Option<&str>,
+        title: &str,
+    ) -> Result<Option<UrlFile>> {
+        let directory = download_path(&self.game(game_selection)?.path_mods)?;
+        let progress = Progress::new(1);
+        let bar = progress.download(title);
+        let download = or_cancelled(
+            &self.cancellation,
+            self.remote.download_url(url, version, &bar, &directory),
+        )
+        .await;
+        bar.finish(match &download {
+            Ok(Some(_)) => "done",
+            Ok(None) => "unchanged",
+            Err(error) if is_cancelled(error) => "cancelled",
+            Err(_) => "failed",
+        });
+        drop(bar);
+        progress.finish();
+        download
+    }
+
+    /// Clones `url` into `repository`, removing what was cloned if it fails or is cancelled.
+    async fn clone_repository(
+        &self,
+        url: &str,
+        reference: Option<&str>,
+        repository: &Path,
+    ) -> Result<()> {
+        let cloned = or_cancelled(&self.cancellation, git::clone(url, reference, repository)).await;
+        if cloned.is_err() && repository.exists() {
+            let _removed = fs::remove_dir_all(repository);
+        }
+        cloned
+    }
+
+    /// Installs the checked out state of the cloned repository of a git mod and saves its commit as the version.
+    async fn install_repository(
+        &mut self,
+        game_selection: usize,
+        item_id: u64,
+        title: String,
+        source: Source,
+    ) -> Result<Descriptor> {
+        let repository = repository_path(&self.game(game_selection)?.path_mods, item_id);
+        let folder = match &source {
+            Source::Git {
+                subdir: Some(subdir),
+                ..
+            } => repository.join(subdir.trim_matches('/')),
+            _ => repository.clone(),
+        };
+        if !folder.is_dir() {
+            bail!("{} does not exist in the repository!", folder.display());
+        }
+
+        let mut item = Mod::new(item_id, title, git::head_time(&repository).await?, source);
+        item.version = Some(git::head(&repository).await?);
+        self.install_download(game_selection, item, Download::Directory(folder))
+    }
+
+    /// Names a newly installed mod `title` or otherwise after its descriptor and returns the name.
+    fn name_mod(
+        &mut self,
+        game_selection: usize,
+        item_id: u64,
+        title: Option<String>,
+        descriptor: Descriptor,
+    ) -> Result<String> {
+        let title = title.or(descriptor.name).unwrap_or_default();
+        if let Some(item_mod) = self
+            .game_mut(game_selection)?
+            .mods
+            .get_mut(&item_id.to_string())
+        {
+            item_mod.title = title.clone();
+        }
+        self.save_config()?;
+        Ok(title)
+    }
+
+    /// Reinstalls a mod which isn't from the workshop if its source changed since it was installed.
+    pub(super) async fn update_from_source(
+        &mut self,
+        game_selection: usize,
+        item_id: u64,
+    ) -> Result<()> {
+        let game = self.game(game_selection)?;
+        let game_title = game.title.clone();
+        let item_mod = game
+            .mods
+            .get(&item_id.to_string())
+            .cloned()
+            .ok_or_else(|| anyhow!("Mod {} is not installed for {}!", item_id, game.title))?;
+
+        if item_mod.pinned {
+            println!(
+                "Mod {} for {} is pinned, it is not updated.",
+                item_mod.title, game_title
+            );
+            return Ok(());
+        }
+
+        let updated = match item_mod.source.clone() {
+            Source::Workshop => bail!("{} is a workshop mod!", item_mod.title),
+            Source::Local { path } => self.update_local(game_selection, &item_mod, path)?,
+            Source::Git { url, reference, .. } => {
+                self.update_git(game_selection, &item_mod, &url, reference.as_deref())
+                    .await?
+            }
+            Source::Url { url, sha256 } => {
+                self.update_url(game_selection, &item_mod, &url, sha256.as_deref())
+                    .await?
+            }
+        };
+
+        if !updated {
+            println!(
+                "Mod {} for {} is already up to date!",
+                item_mod.title, game_title
+            );
+        }
+        Ok(())
+    }
+
+    /// Reinstalls a local mod if its archive or folder changed. Returns whether it was reinstalled.
+    fn update_local(
+        &mut self,
+        game_selection: usize,
+        item_mod: &Mod,
+        path: String,
+    ) -> Result<bool> {
+        let source_path = Path::new(&path);
+        if !source_path.exists() {
+            bail!(
+                "Could not check mod {} for updates, {} no longer exists.",
+                item_mod.title,
+                path
+            );
+        }
+        let modified = modified_time(source_path)?;
+        if modified <= item_mod.time_updated {
+            return Ok(false);
+        }
+
+        println!("Updating mod {} from {}!", item_mod.title, path);
+        let download = read_local(source_path)?;
+        let _descriptor = self.install_download(
+            game_selection,
+            Mod::new(
+                item_mod.id,
+                item_mod.title.clone(),
+                modified,
+                Source::Local { path },
+            ),
+            download,
+        )?;
+        Ok(true)
+    }
+
+    /// Fetches and fast-forwards the repository of a git mod and reinstalls it if the commit changed.
+    /// Returns whether it was reinstalled.
+    async fn update_git(
+        &mut self,
+        game_selection: usize,
+        item_mod: &Mod,
+        url: &str,
+        reference: Option<&str>,
+    ) -> Result<bool> {
+        let repository = repository_path(&self.game(game_selection)?.path_mods, item_mod.id);
+        if repository.exists() {
+            or_cancelled(&self.cancellation, git::update(&repository, reference)).await?;
+        } else {
+            println!("Cloning {} again.", url);
+            self.clone_repository(url, reference, &repository).await?;
+        }
+
+        let commit = git::head(&repository).await?;
+        if item_mod.version.as_deref() == Some(commit.as_str()) {
+            return Ok(false);
+        }
+
+        println!(
+            "Updating mod {} to commit {}!",
+            item_mod.title,
+            &commit[..commit.len().min(10)]
+        );
+        let _descriptor = self
+            .install_repository(
+                game_selection,
+                item_mod.id,
+                item_mod.title.clone(),
+                item_mod.source.clone(),
+            )
+            .await?;
+        Ok(true)
+    }
+
+    /// Downloads the archive of a url mod again if the server reports a newer version than the installed one.
+    ///
+    /// Servers which send neither `ETag` nor `Last-Modified` send the whole archive every time,
+    /// so the mod is only reinstalled if the archive's hash changed. Returns whether it was reinstalled.
+    async fn update_url(
+        &mut self,
+        game_selection: usize,
+        item_mod: &Mod,
+        url: &str,
+        sha256: Option<&str>,
+    ) -> Result<bool> {
+        let UrlFile { file, version } = match self
+            .fetch_url(
+                game_selection,
+                url,
+                item_mod.version.as_deref(),
+                &item_mod.title,
+            )
+            .await?
+        {
+            Some(download) => download,
+            None => return Ok(false),
+        };
+        let hash = sha256_file(file.path())?;
+        if item_mod.sha256.as_deref() == Some(hash.as_str()) {
+            if let Some(installed) = self
+                .game_mut(game_selection)?
+                .mods
+                .get_mut(&item_mod.id.to_string())
+            {
+                installed.version = version;
+            }
+            self.save_config()?;
+            return Ok(false);
+        }
+        if let Some(sha256) = sha256 {
+            verify_sha256(file.path(), sha256).map_err(|error| {
+                anyhow!(
+                    "Could not update mod {}: {} Add it again with the new hash to update it.",
+                    item_mod.title,
+                    error
+                )
+            })?;
+        }
+
+        println!("Updating mod {} from {}!", item_mod.title, url);
+        let mut item = Mod::new(
+            item_mod.id,
+            item_mod.title.clone(),
+            now(),
+            item_mod.source.clone(),
+        );
+        item.version = version;
+        item.sha256 = Some(hash);
+        let _descriptor = self.install_download(game_selection, item, Download::Archive(file))?;
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs,
+        io::{BufRead, BufReader, Cursor, Write},
+        net::TcpListener,
+        path::{Path, PathBuf},
+        process::Command,
+        sync::{Arc, Mutex},
+        thread,
+        time::{Duration, SystemTime, UNIX_EPOCH},
+    };
+
+    use tempfile::TempDir;
+    use zip::{write::FileOptions, ZipWriter};
+
+    use crate::{filesystem::repository_path, ModManager};
+
+    /// Runs git with `args` in `directory`.
+    fn run_git(directory: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .args(["-c", "user.name=Test", "-c", "user.email=test@example.com"])
+            .args(args)
+            .current_dir(directory)
+            .status()
+            .unwrap();
+        assert!(status.success(), "git {:?} failed", args);
+    }
+
+    /// Commits `version` as the mod's version in the working copy `work` and pushes it.
+    fn push_version(work: &Path, version: &str) {
+        fs::create_dir_all(work.join("common")).unwrap();
+        fs::write(work.join("descriptor.mod"), "name=\"Git Mod\"\n").unwrap();
+        fs::write(work.join("common").join("version.txt"), version).unwrap();
+        run_git(work, &["add", "-A"]);
+        run_git(work, &["commit", "--quiet", "-m", version]);
+        run_git(work, &["push", "--quiet", "origin", "main"]);
+    }
+
+    /// Creates the bare repository `dir/origin.git` and the working copy `dir/work`, which pushed version 1.
+    fn origin(dir: &Path) -> (PathBuf, PathBuf) {
+        let origin = dir.join("origin.git");
+        let work = dir.join("work");
+        fs::create_dir_all(&origin).unwrap();
+        fs::create_dir_all(&work).unwrap();
+        run_git(&origin, &["init", "--quiet", "--bare"]);
+        run_git(&origin, &["symbolic-ref", "HEAD", "refs/heads/main"]);
+        run_git(&work, &["init", "--quiet"]);
+        run_git(&work, &["checkout", "--quiet", "-b", "main"]);
+        run_git(
+            &work,
+            &["remote", "add", "origin", origin.to_str().unwrap()],
+        );
+        push_version(&work, "1");
+        (origin, work)
+    }
+
+    #[tokio::test]
+    async fn git_mods_are_updated_to_new_commits() {
+        let dir = TempDir::new().unwrap();
+        let (origin, work) = origin(dir.path());
+
+        let mut manager = ModManager::for_test(dir.path());
+        let title = manager
+            .install_git(0, origin.to_str().unwrap(), None, None, None)
+            .await
+            .unwrap();
+        assert_eq!(title, "Git Mod");
+        let item_mod = manager
+            .game(0)
+            .unwrap()
+            .mods
+            .values()
+            .next()
+            .unwrap()
+            .clone();
+        let installed = Path::new(&manager.game(0).unwrap().path_mods)
+            .join(item_mod.id.to_string())
+            .join("common")
+            .join("version.txt");
+        assert_eq!(fs::read_to_string(&installed).unwrap(), "1");
+
+        manager.update_mod(item_mod.id, 0).await.unwrap();
+        let unchanged = &manager.game(0).unwrap().mods[&item_mod.id.to_string()];
+        assert_eq!(unchanged.version, item_mod.version);
+
+        push_version(&work, "2");
+        manager.update_mod(item_mod.id, 0).await.unwrap();
+        let updated = &manager.game(0).unwrap().mods[&item_mod.id.to_string()];
+        assert_ne!(updated.version, item_mod.version);
+        assert!(updated.version.is_some());
+        assert_eq!(fs::read_to_string(&installed).unwrap(), "2");
+    }
+
+    #[tokio::test]
+    async fn failed_clones_keep_the_installed_repository() {
+        let dir = TempDir::new().unwrap();
+        let (origin, work) = origin(dir.path());
+
+        let mut manager = ModManager::for_test(dir.path());
+        let url = origin.to_str().unwrap().to_string();
+        let _title = manager
+            .install_git(0, &url, None, None, None)
+            .await
+            .unwrap();
+        let item_id = manager.game(0).unwrap().mods.values().next().unwrap().id;
+        let repository = repository_path(&manager.game(0).unwrap().path_mods, item_id);
+
+        // Installing again replaces the repository with a fresh clone.
+        push_version(&work, "2");
+        let _title = manager
+            .install_git(0, &url, None, None, None)
+            .await
+            .unwrap();
+        assert_eq!(
+            fs::read_to_string(repository.join("common").join("version.txt")).unwrap(),
+            "2"
+        );
+
+        fs::rename(&origin, dir.path().join("moved.git")).unwrap();
+        assert!(manager
+            .install_git(0, &url, None, None, None)
+            .await
+            .is_err());
+        assert_eq!(
+            fs::read_to_string(repository.join("common").join("version.txt")).unwrap(),
+            "2"
+        );
+        assert!(!repository.with_extension("clone").exists());
+        assert!(manager.update_mod(item_id, 0).await.is_err());
+        assert!(repository.join(".git").is_dir());
+    }
+
+    #[tokio::test]
+    async fn references_starting_with_a_dash_are_rejected() {
+        let dir = TempDir::new().unwrap();
+        let mut manager = ModManager::for_test(dir.path());
+        let error = manager
+            .install_git(0, "origin", Some("--orphan".to_string()), None, None)
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("not a valid branch or tag"));
+        assert!(manager.game(0).unwrap().mods.is_empty());
+    }
+
+    /// Sets the modification time of `path` and everything inside it to `time`.
+    fn set_modified(path: &Path, time: SystemTime) {
+        if path.is_dir() {
+            for entry in fs::read_dir(path).unwrap() {
+                set_modified(&entry.unwrap().path(), time);
+            }
+        }
+        fs::File::open(path).unwrap().set_modified(time).unwrap();
+    }
+
+    /// Writes `version` into the local mod `folder` and sets the modification time of all its files to `time`.
+    fn write_local_version(folder: &Path, version: &str, time: SystemTime) {
+        fs::create_dir_all(folder.join("common")).unwrap();
+        fs::write(folder.join("descriptor.mod"), "name=\"Local Mod\"\n").unwrap();
+        fs::write(folder.join("common").join("version.txt"), version).unwrap();
+        set_modified(folder, time);
+    }
+
+    #[tokio::test]
+    async fn local_mods_are_only_reinstalled_when_they_were_modified() {
+        let dir = TempDir::new().unwrap();
+        let folder = dir.path().join("local");
+        let installed_at = UNIX_EPOCH + Duration::from_secs(1_600_000_000);
+        write_local_version(&folder, "1", installed_at);
+
+        let mut manager = ModManager::for_test(dir.path());
+        let title = manager
+            .install_local(0, folder.to_str().unwrap(), None)
+            .unwrap();
+        assert_eq!(title, "Local Mod");
+        let item_mod = manager
+            .game(0)
+            .unwrap()
+            .mods
+            .values()
+            .next()
+            .unwrap()
+            .clone();
+        assert_eq!(item_mod.time_updated, 1_600_000_000);
+        let installed = Path::new(&manager.game(0).unwrap().path_mods)
+            .join(item_mod.id.to_string())
+            .join("common")
+            .join("version.txt");
+        assert_eq!(fs::read_to_string(&installed).unwrap(), "1");
+
+        // A change which keeps the modification time isn't noticed.
+        write_local_version(&folder, "2", installed_at);
+        manager.update_mod(item_mod.id, 0).await.unwrap();
+        assert_eq!(fs::read_to_string(&installed).unwrap(), "1");
+
+        write_local_version(&folder, "2", installed_at + Duration::from_secs(60));
+        manager.update_mod(item_mod.id, 0).await.unwrap();
+        assert_eq!(fs::read_to_string(&installed).unwrap(), "2");
+        let updated = &manager.game(0).unwrap().mods[&item_mod.id.to_string()];
+        assert_eq!(updated.time_updated, 1_600_000_060);
+        assert_eq!(updated.source, item_mod.source);
+    }
+
+    #[tokio::test]
+    async fn local_mods_whose_folder_is_gone_are_not_updated() {
+        let dir = TempDir::new().unwrap();
+        let folder = dir.path().join("local");
+        write_local_version(&folder, "1", SystemTime::now());
+        let mut manager = ModManager::for_test(dir.path());
+        let _title = manager
+            .install_local(0, folder.to_str().unwrap(), None)
+            .unwrap();
+        let item_id = manager.game(0).unwrap().mods.values().next().unwrap().id;
+
+        fs::remove_dir_all(&folder).unwrap();
+        let error = manager.update_mod(item_id, 0).await.unwrap_err();
+        assert!(format!("{:#}", error).contains("no longer exists"));
+        assert!(manager
+            .game(0)
+            .unwrap()
+            .mods
+            .contains_key(&item_id.to_string()));
+    }
+
+    /// What the test server sends.
+    #[derive(Default)]
+    struct Served {
+        /// Body of every response.
+        body: Vec<u8>,
+        /// `ETag` of the body, which is also checked against `If-None-Match`.
+        etag: Option<String>,
+        /// Number of responses which sent the body.
+        downloads: usize,
+    }
+
+    /// Starts an HTTP server on localhost which answers every request with `served` and returns its address.
+    fn serve(served: Arc<Mutex<Served>>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = format!("http://{}/mod.zip", listener.local_addr().unwrap());
+        let _server = thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut if_none_match = None;
+                for line in BufReader::new(stream.try_clone().unwrap()).lines() {
+                    let line = line.unwrap();
+                    if line.is_empty() {
+                        break;
+                    }
+                    if let Some((name, value)) = line.split_once(':') {
+                        if name.eq_ignore_ascii_case("if-none-match") {
+                            if_none_match = Some(value.trim().to_string());
+                        }
+                    }
+                }
+
+                let mut served = served.lock().unwrap();
+                if served.etag.is_some() && if_none_match == served.etag {
+                    write!(
+                        stream,
+                        "HTTP/1.1 304 Not Modified\r\nConnection: close\r\n\r\n"
+                    )
+                    .unwrap();
+                    continue;
+                }
+                served.downloads += 1;
+                let etag = served
+                    .etag
+                    .as_ref()
+                    .map(|etag| format!("ETag: {}\r\n", etag))
+                    .unwrap_or_default();
+                write!(
+                    stream,
+                    "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n{}Connection: close\r\n\r\n",
+                    served.body.len(),
+                    etag
+                )
+                .unwrap();
+                stream.write_all(&served.body).unwrap();
+            }
+        });
+        address
+    }
+
+    /// Returns a zip archive of a mod whose `common/version.txt` contains `version`.
+    fn mod_archive(version: &str) -> Vec<u8> {
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        writer
+            .start_file("descriptor.mod", FileOptions::default())
+            .unwrap();
+        writer.write_all(b"name=\"Url Mod\"\n").unwrap();
+        writer
+            .start_file("common/version.txt", FileOptions::default())
+            .unwrap();
+        writer.write_all(version.as_bytes()).unwrap();
+        writer.finish().unwrap().into_inner()
+    }
+
+    /// Installs the mod served at `address` and returns its id.
+    async fn install(manager: &mut ModManager, address: &str) -> u64 {
+        let title = manager.install_url(0, address, None, None).await.unwrap();
+        assert_eq!(title, "Url Mod");
+        manager.game(0).unwrap().mods.values().next().unwrap().id
+    }
+
+    /// Returns the content of `common/version.txt` of the installed mod `item_id`.
+    fn installed_version(manager: &ModManager, item_id: u64) -> String {
+        let path = Path::new(&manager.game(0).unwrap().path_mods)
+            .join(item_id.to_string())
+            .join("common")
+            .join("version.txt");
+        fs::read_to_string(path).unwrap()
+    }
+
+    #[tokio::test]
+    async fn url_mods_are_not_downloaded_again_while_the_etag_matches() {
+        let dir = TempDir::new().unwrap();
+        let served = Arc::new(Mutex::new(Served {
+            body: mod_archive("1"),
+            etag: Some("\"v1\"".to_string()),
+            downloads: 0,
+        }));
+        let address = serve(Arc::clone(&served));
+        let mut manager = ModManager::for_test(dir.path());
+        let item_id = install(&mut manager, &address).await;
+        assert_eq!(
+            manager.game(0).unwrap().mods[&item_id.to_string()]
+                .version
+                .as_deref(),
+            Some("\"v1\"")
+        );
+
+        manager.update_mod(item_id, 0).await.unwrap();
+        assert_eq!(served.lock().unwrap().downloads, 1);
+        assert!(manager
+            .mod_versions(0, &item_id.to_string())
+            .unwrap()
+            .is_empty());
+
+        {
+            let mut served = served.lock().unwrap();
+            served.body = mod_archive("2");
+            served.etag = Some("\"v2\"".to_string());
+        }
+        manager.update_mod(item_id, 0).await.unwrap();
+        assert_eq!(served.lock().unwrap().downloads, 2);
+        assert_eq!(installed_version(&manager, item_id), "2");
+        assert_eq!(
+            manager.game(0).unwrap().mods[&item_id.to_string()]
+                .version
+                .as_deref(),
+            Some("\"v2\"")
+        );
+        assert_eq!(
+            manager.mod_versions(0, &item_id.to_string()).unwrap().len(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn url_mods_without_validators_are_only_reinstalled_when_the_archive_changed() {
+        let dir = TempDir::new().unwrap();
+        let served = Arc::new(Mutex::new(Served {
+            body: mod_archive("1"),
+            ..Served::default()
+        }));
+        let address = serve(Arc::clone(&served));
+        let mut manager = ModManager::for_test(dir.path());
+        let item_id = install(&mut manager, &address).await;
+        let installed = manager.game(0).unwrap().mods[&item_id.to_string()].clone();
+        assert_eq!(installed.version, None);
+        assert!(installed.sha256.is_some());
+
+        for _ in 0..3 {
+            manager.update_mod(item_id, 0).await.unwrap();
+        }
+        assert_eq!(served.lock().unwrap().downloads, 4);
+        assert!(manager
+            .mod_versions(0, &item_id.to_string())
+            .unwrap()
+            .is_empty());
+        let unchanged = &manager.game(0).unwrap().mods[&item_id.to_string()];
+        assert_eq!(unchanged.time_updated, installed.time_updated);
+
+        served.lock().unwrap().body = mod_archive("2");
+        manager.update_mod(item_id, 0).await.unwrap();
+        assert_eq!(installed_version(&manager, item_id), "2");
+        assert_eq!(
+            manager.mod_versions(0, &item_id.to_string()).unwrap().len(),
+            1
+        );
+        assert_ne!(
+            manager.game(0).unwrap().mods[&item_id.to_string()].sha256,
+            installed.sha256
+        );
+    }
+}
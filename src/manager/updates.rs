@@ -0,0 +1,333 @@
+//! Updating installed mods.
+
+use anyhow::{anyhow, bail, Result};
+use futures_util::{stream, StreamExt};
+
+use super::ModManager;
+use crate::{
+    cancel::{is_cancelled, or_cancelled, Cancelled},
+    filesystem::download_path,
+    progress::{Printer, Progress},
+    remote::ItemInfo,
+    source::Source,
+    Mod,
+};
+
+/// A mod with a newer version in the workshop.
+struct Update {
+    /// Position of the game the mod is installed for.
+    game_selection: usize,
+    /// Id of the mod.
+    item_id: u64,
+    /// Metadata of the newer version.
+    item_info: ItemInfo,
+}
+
+impl ModManager {
+    /// Updates the mod `item_id` of the game at `game_selection` if a newer version is available.
+    ///
+    /// Workshop mods are compared with the workshop, other mods with their source. Pinned mods are left alone.
+    pub async fn update_mod(&mut self, item_id: u64, game_selection: usize) -> Result<()> {
+        let game = self.game(game_selection)?;
+        let item_mod = game
+            .mods
+            .get(&item_id.to_string())
+            .ok_or_else(|| anyhow!("Mod {} is not installed for {}!", item_id, game.title))?;
+        if !item_mod.source.is_workshop() {
+            return self.update_from_source(game_selection, item_id).await;
+        }
+
+        let item_info = self.get_item_info(item_id).await?;
+        let updates = self.outdated(game_selection, vec![(item_id, item_info)])?;
+        self.download_updates(updates, Vec::new()).await
+    }
+
+    /// Updates the collections and all mods of the game at `game_selection`.
+    pub async fn update_game_mods(&mut self, game_selection: usize) -> Result<()> {
+        let mut failures = Vec::new();
+        let updates = self.check_game(game_selection, &mut failures).await?;
+        self.download_updates(updates, failures).await
+    }
+
+    /// Updates the collections and mods of all games, downloading the mods of all games at the same time.
+    ///
+    /// A game which can't be checked doesn't stop the others, its failures are listed at the end.
+    pub async fn update_all_mods(&mut self) -> Result<()> {
+        let mut updates = Vec::new();
+        let mut failures = Vec::new();
+        for game_selection in 0..self.config.games.len() {
+            updates.extend(self.check_game(game_selection, &mut failures).await?);
+        }
+        self.download_updates(updates, failures).await
+    }
+
+    /// Brings the collections and the mods which aren't from the workshop of the game at `game_selection` up to date
+    /// and returns its outdated workshop mods.
+    ///
+    /// Everything which fails is added to `failures` with the name of what failed, only cancelling stops the check.
+    async fn check_game(
+        &mut self,
+        game_selection: usize,
+        failures: &mut Vec<(String, anyhow::Error)>,
+    ) -> Result<Vec<Update>> {
+        let title = self.game(game_selection)?.title.clone();
+        match self.sync_collections(game_selection).await {
+            Ok(failed) => failures.extend(failed),
+            Err(error) if is_cancelled(&error) => return Err(error),
+            Err(error) => failures.push((format!("Collections of {}", title), error)),
+        }
+        failures.extend(self.sync_sources(game_selection).await?);
+        match self.find_updates(game_selection).await {
+            Ok(updates) => Ok(updates),
+            Err(error) if is_cancelled(&error) => Err(error),
+            Err(error) => {
+                failures.push((format!("Workshop mods of {}", title), error));
+                Ok(Vec::new())
+            }
+        }
+    }
+
+    /// Updates the mods of the game at `game_selection` which aren't from the workshop.
+    ///
+    /// A mod which can't be updated is skipped, the failures are returned with the mod's name.
+    async fn sync_sources(
+        &mut self,
+        game_selection: usize,
+    ) -> Result<Vec<(String, anyhow::Error)>> {
+        let mods: Vec<(u64, String)> = self
+            .game(game_selection)?
+            .mods
+            .values()
+            .filter(|item_mod| !item_mod.source.is_workshop())
+            .map(|item_mod| (item_mod.id, item_mod.title.clone()))
+            .collect();
+
+        let mut failures = Vec::new();
+        for (item_id, title) in mods {
+            if let Err(error) = self.update_from_source(game_selection, item_id).await {
+                if is_cancelled(&error) {
+                    return Err(error);
+                }
+                failures.push((title, error));
+            }
+        }
+        Ok(failures)
+    }
+
+    /// Checks all workshop mods of the game at `game_selection` for newer versions.
+    async fn find_updates(&self, game_selection: usize) -> Result<Vec<Update>> {
+        let ids: Vec<u64> = self
+            .game(game_selection)?
+            .mods
+            .values()
+            .filter(|item_mod| item_mod.source.is_workshop())
+            .map(|item_mod| item_mod.id)
+            .collect();
+
+        let mut infos = self.get_items_info(&ids).await?;
+        let found = ids
+            .into_iter()
+            .filter_map(|item_id| {
+                let item_info = infos.remove(&item_id);
+                if item_info.is_none() {
+                    println!(
+                        "Could not check mod {} for updates, it is no longer available on the workshop.",
+                        item_id
+                    );
+                }
+                item_info.map(|item_info| (item_id, item_info))
+            })
+            .collect();
+        self.outdated(game_selection, found)
+    }
+
+    /// Keeps the mods whose workshop version is newer than the installed one.
+    fn outdated(&self, game_selection: usize, infos: Vec<(u64, ItemInfo)>) -> Result<Vec<Update>> {
+        let game = self.game(game_selection)?;
+        let mut updates = Vec::new();
+
+        for (item_id, item_info) in infos {
+            let modif = game
+                .mods
+                .get(&item_id.to_string())
+                .ok_or_else(|| anyhow!("Mod {} is not installed for {}!", item_id, game.title))?;
+
+            if modif.pinned {
+                println!(
+                    "Mod {} for {} is pinned, it is not updated.",
+                    modif.title, game.title
+                );
+            } else if modif.time_updated >= item_info.time_updated {
+                println!(
+                    "Mod {} for {} is already up to date!",
+                    modif.title, game.title
+                );
+            } else {
+                println!("Updating mod {} for {}!", modif.title, game.title);
+                updates.push(Update {
+                    game_selection,
+                    item_id,
+                    item_info,
+                });
+            }
+        }
+        Ok(updates)
+    }
+
+    /// Downloads the updates concurrently and installs each one as soon as its download finished.
+    ///
+    /// At most `concurrency` downloads from the configuration run at the same time.
+    /// Installing happens one mod at a time, so no two mods are written into a mod folder at once.
+    /// A failed update doesn't stop the others, the failures are listed at the end together with the
+    /// `checks` which failed earlier in the run. Each download has its own token from [`ModManager::downloads`],
+    /// a single cancelled download only skips its mod.
+    async fn download_updates(
+        &mut self,
+        updates: Vec<Update>,
+        checks: Vec<(String, anyhow::Error)>,
+    ) -> Result<()> {
+        let count = updates.len();
+        let mut failures = Vec::new();
+        let concurrency = self.config.concurrency.max(1);
+        let remote = self.remote.clone();
+        let cancellation = self.cancellation.clone();
+        let tokens = self.downloads.clone();
+        let mut directories = Vec::with_capacity(updates.len());
+        for update in &updates {
+            directories.push(download_path(&self.game(update.game_selection)?.path_mods)?);
+        }
+        let progress = Progress::new(updates.len());
+        self.printer = progress.printer();
+
+        let mut downloads = stream::iter(updates.into_iter().zip(directories))
+            .map(|(update, directory)| {
+                let remote = remote.clone();
+                let cancellation = cancellation.clone();
+                let tokens = tokens.clone();
+                let bar = progress.download(&update.item_info.title);
+                async move {
+                    let item_id = update.item_id;
+                    let game_selection = update.game_selection;
+                    let title = update.item_info.title.clone();
+                    let app_id = update.item_info.consumer_app_id;
+                    let download = tokio::spawn(async move {
+                        let token = tokens.start(game_selection, item_id, &title, &cancellation);
+                        let download = or_cancelled(
+                            &token,
+                            remote.download_item(item_id, app_id, &bar, &directory),
+                        )
+                        .await;
+                        tokens.finish(game_selection, item_id);
+                        bar.finish(match &download {
+                            Ok(_) => "done",
+                            Err(error) if is_cancelled(error) => "cancelled",
+                            Err(_) => "failed",
+                        });
+                        download
+                    })
+                    .await;
+                    (update, download)
+                }
+            })
+            .buffer_unordered(concurrency);
+
+        while let Some((update, download)) = downloads.next().await {
+            let title = update.item_info.title.clone();
+            let installed = match download {
+                Ok(Ok(_file)) if self.cancellation.is_cancelled() => Err(Cancelled.into()),
+                Ok(Ok(file)) => self
+                    .install_download(
+                        update.game_selection,
+                        Mod::new(
+                            update.item_id,
+                            update.item_info.title,
+                            update.item_info.time_updated,
+                            Source::Workshop,
+                        ),
+                        file,
+                    )
+                    .map(|_descriptor| ()),
+                Ok(Err(error)) => Err(error),
+                Err(error) => Err(error.into()),
+            };
+            match installed {
+                Ok(()) => self.printer.println("Mod updated!."),
+                Err(error) => failures.push((title, error)),
+            }
+        }
+        drop(downloads);
+        self.printer = Printer::default();
+        progress.finish();
+
+        let (cancelled, failures): (Vec<_>, Vec<_>) = failures
+            .into_iter()
+            .partition(|(_, error)| is_cancelled(error));
+        if !checks.is_empty() || !failures.is_empty() {
+            println!("These mods could not be updated:");
+            for (title, error) in checks.iter().chain(&failures) {
+                println!("    {}: {:#}", title, error);
+            }
+        }
+        if !cancelled.is_empty() && self.cancellation.is_cancelled() {
+            println!(
+                "{} mods were not updated because the update was cancelled.",
+                cancelled.len()
+            );
+            return Err(Cancelled.into());
+        }
+        if !cancelled.is_empty() {
+            println!("These downloads were cancelled:");
+            for (title, _) in &cancelled {
+                println!("    {}", title);
+            }
+        }
+        match (failures.len(), checks.len()) {
+            (0, 0) => Ok(()),
+            (failed, 0) => bail!("{} of {} updates failed!", failed, count),
+            (0, checks) => bail!("{} checks for updates failed!", checks),
+            (failed, checks) => bail!(
+                "{} of {} updates and {} checks for updates failed!",
+                failed,
+                count,
+                checks
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+
+    use super::*;
+
+    #[test]
+    fn only_outdated_mods_which_are_not_pinned_are_updated() {
+        let dir = TempDir::new().unwrap();
+        let mut manager = ModManager::for_test(dir.path());
+        let mods = &mut manager.game_mut(0).unwrap().mods;
+        for &(id, pinned) in &[(1, false), (2, true), (3, false)] {
+            let mut item_mod = Mod::new(id, format!("Mod {}", id), 10, Source::Workshop);
+            item_mod.pinned = pinned;
+            let _old = mods.insert(id.to_string(), item_mod);
+        }
+        let info = |time_updated: u64| ItemInfo {
+            title: String::from("Mod"),
+            time_updated,
+            consumer_app_id: 281990,
+            children: Vec::new(),
+        };
+
+        let updates = manager
+            .outdated(0, vec![(1, info(20)), (2, info(20)), (3, info(10))])
+            .unwrap();
+        assert_eq!(
+            updates
+                .iter()
+                .map(|update| update.item_id)
+                .collect::<Vec<_>>(),
+            vec![1]
+        );
+        assert!(manager.outdated(0, vec![(4, info(20))]).is_err());
+    }
+}
@@ -0,0 +1,184 @@
+//! Installing archived earlier versions of mods and pinning mods to a version.
+
+use std::fs;
+
+use anyhow::{anyhow, bail, Result};
+use tempfile::NamedTempFile;
+
+use super::ModManager;
+use crate::{
+    backend::Download,
+    filesystem::{archived_versions, version_archive},
+    Mod,
+};
+
+impl ModManager {
+    /// Returns the mod with the configuration key `key` of the game at `game_selection`.
+    fn installed_mod(&self, game_selection: usize, key: &str) -> Result<Mod> {
+        let game = self.game(game_selection)?;
+        game.mods
+            .get(key)
+            .cloned()
+            .ok_or_else(|| anyhow!("Mod {} is not installed for {}!", key, game.title))
+    }
+
+    /// Returns the update times of the archived versions of the mod with the configuration key `key`, the newest first.
+    pub fn mod_versions(&self, game_selection: usize, key: &str) -> Result<Vec<u64>> {
+        let item_mod = self.installed_mod(game_selection, key)?;
+        archived_versions(
+            self.cache_dir()?,
+            &self.game(game_selection)?.path_mods,
+            item_mod.id,
+        )
+    }
+
+    /// Reinstalls the newest archived version of a mod which is older than the installed one and pins the mod to it.
+    ///
+    /// Returns the update time of the version now installed.
+    pub fn rollback_mod(&mut self, game_selection: usize, key: &str) -> Result<u64> {
+        let item_mod = self.installed_mod(game_selection, key)?;
+        let time_updated = self
+            .mod_versions(game_selection, key)?
+            .into_iter()
+            .find(|&time_updated| time_updated < item_mod.time_updated)
+            .ok_or_else(|| anyhow!("No earlier version of {} is archived!", item_mod.title))?;
+
+        self.pin_mod(game_selection, key, Some(time_updated))?;
+        Ok(time_updated)
+    }
+
+    /// Pins a mod so updates leave it at its version.
+    ///
+    /// If `time_updated` is another version than the installed one, that version is installed from its archive first.
+    /// The replaced version is archived, so it can be installed again later.
+    pub fn pin_mod(
+        &mut self,
+        game_selection: usize,
+        key: &str,
+        time_updated: Option<u64>,
+    ) -> Result<()> {
+        let item_mod = self.installed_mod(game_selection, key)?;
+
+        if let Some(time_updated) = time_updated.filter(|&time| time != item_mod.time_updated) {
+            let path_mods = self.game(game_selection)?.path_mods.clone();
+            let archive = version_archive(self.cache_dir()?, &path_mods, item_mod.id, time_updated);
+            if !archive.exists() {
+                bail!(
+                    "Version {} of {} is not archived!",
+                    time_updated,
+                    item_mod.title
+                );
+            }
+
+            // Installing archives the current version, which may delete the oldest archive.
+            let file = NamedTempFile::new()?;
+            let _bytes = fs::copy(&archive, file.path())?;
+            let _descriptor = self.install_download(
+                game_selection,
+                Mod {
+                    time_updated,
+                    ..item_mod.clone()
+                },
+                Download::Archive(file),
+            )?;
+        }
+
+        self.set_pinned(game_selection, key, true)
+    }
+
+    /// Lets updates move a pinned mod forward again.
+    pub fn unpin_mod(&mut self, game_selection: usize, key: &str) -> Result<()> {
+        let _item_mod = self.installed_mod(game_selection, key)?;
+        self.set_pinned(game_selection, key, false)
+    }
+
+    /// Saves whether the mod with the configuration key `key` is pinned.
+    fn set_pinned(&mut self, game_selection: usize, key: &str, pinned: bool) -> Result<()> {
+        if let Some(item_mod) = self.game_mut(game_selection)?.mods.get_mut(key) {
+            item_mod.pinned = pinned;
+        }
+        self.save_config()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::{source::Source, Config};
+
+    /// Installs version `time_updated` of the mod 1 with `content` in `common/file.txt`.
+    fn install(manager: &mut ModManager, dir: &Path, time_updated: u64, content: &str) {
+        let folder = dir.join(time_updated.to_string());
+        fs::create_dir_all(folder.join("common")).unwrap();
+        fs::write(folder.join("common").join("file.txt"), content).unwrap();
+        let mut item = Mod::new(1, String::from("Mod"), time_updated, Source::Workshop);
+        item.version = Some(String::from("v1"));
+        item.sha256 = Some(String::from("hash"));
+        let _descriptor = manager
+            .install_download(0, item, Download::Directory(folder))
+            .unwrap();
+    }
+
+    /// Returns the content of `common/file.txt` of the installed mod 1.
+    fn installed(manager: &ModManager) -> String {
+        let path_mods = &manager.game(0).unwrap().path_mods;
+        fs::read_to_string(Path::new(path_mods).join("1/common/file.txt")).unwrap()
+    }
+
+    #[test]
+    fn rolled_back_mods_are_pinned_to_the_earlier_version() {
+        let dir = TempDir::new().unwrap();
+        let mut manager = ModManager::for_test(dir.path());
+        install(&mut manager, dir.path(), 10, "first");
+        assert!(manager.rollback_mod(0, "1").is_err());
+        install(&mut manager, dir.path(), 20, "second");
+        assert_eq!(manager.mod_versions(0, "1").unwrap(), vec![10]);
+
+        assert_eq!(manager.rollback_mod(0, "1").unwrap(), 10);
+        assert_eq!(installed(&manager), "first");
+        let item_mod = &manager.game(0).unwrap().mods["1"];
+        assert_eq!(item_mod.time_updated, 10);
+        assert!(item_mod.pinned);
+        assert_eq!(manager.mod_versions(0, "1").unwrap(), vec![20, 10]);
+    }
+
+    #[test]
+    fn pinning_to_another_version_installs_it_and_keeps_the_mod_settings() {
+        let dir = TempDir::new().unwrap();
+        let mut manager = ModManager::for_test(dir.path());
+        install(&mut manager, dir.path(), 10, "first");
+        install(&mut manager, dir.path(), 20, "second");
+        assert!(manager.pin_mod(0, "1", Some(15)).is_err());
+        assert!(!manager.game(0).unwrap().mods["1"].pinned);
+
+        manager.pin_mod(0, "1", Some(10)).unwrap();
+        assert_eq!(installed(&manager), "first");
+        let item_mod = &manager.game(0).unwrap().mods["1"];
+        assert_eq!(item_mod.time_updated, 10);
+        assert!(item_mod.pinned);
+        assert_eq!(item_mod.version.as_deref(), Some("v1"));
+        assert_eq!(item_mod.sha256.as_deref(), Some("hash"));
+
+        manager.pin_mod(0, "1", None).unwrap();
+        assert_eq!(installed(&manager), "first");
+        assert_eq!(manager.game(0).unwrap().mods["1"].time_updated, 10);
+    }
+
+    #[test]
+    fn unpinned_mods_are_saved() {
+        let dir = TempDir::new().unwrap();
+        let mut manager = ModManager::for_test(dir.path());
+        install(&mut manager, dir.path(), 10, "first");
+        manager.pin_mod(0, "1", None).unwrap();
+        assert!(Config::load(&dir.path().join("config.toml")).unwrap().games[0].mods["1"].pinned);
+
+        manager.unpin_mod(0, "1").unwrap();
+        assert!(!manager.game(0).unwrap().mods["1"].pinned);
+        assert!(!Config::load(&dir.path().join("config.toml")).unwrap().games[0].mods["1"].pinned);
+        assert!(manager.unpin_mod(0, "2").is_err());
+    }
+}
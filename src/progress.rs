@@ -0,0 +1,186 @@
+//! Progress bars for downloads.
+
+use std::{
+    sync::Arc,
+    thread::{self, JoinHandle},
+};
+
+use indicatif::{MultiProgress, ProgressBar, ProgressDrawTarget, ProgressStyle};
+
+/// Template of the bar of a single download.
+const DOWNLOAD_TEMPLATE: &str =
+    "{prefix:30} [{bar:30}] {bytes}/{total_bytes} {bytes_per_sec} ETA {eta} {msg}";
+
+/// Template of the bar of all downloads together.
+const TOTAL_TEMPLATE: &str =
+    "{prefix:30} [{bar:30.bold}] {bytes}/{total_bytes} {bytes_per_sec} ETA {eta}";
+
+/// Longest title shown in front of a bar.
+const PREFIX_LENGTH: usize = 30;
+
+/// Returns the style of a bar drawn with `template`.
+fn style(template: &str) -> ProgressStyle {
+    ProgressStyle::default_bar()
+        .template(template)
+        .progress_chars("=> ")
+}
+
+/// Shortens `title` so the bars stay aligned.
+fn prefix(title: &str) -> String {
+    title.trim().chars().take(PREFIX_LENGTH).collect()
+}
+
+/// Progress bars of a group of downloads with a bar for all of them together.
+///
+/// The bars are drawn on stderr and hidden if it isn't a terminal.
+pub struct Progress {
+    /// Draws all bars of the group.
+    multi: Arc<MultiProgress>,
+    /// Bar of all downloads together.
+    total: ProgressBar,
+    /// Thread drawing the bars until all of them are finished.
+    drawer: Option<JoinHandle<()>>,
+    /// Whether the bars aren't drawn because stderr isn't a terminal.
+    hidden: bool,
+}
+
+impl Progress {
+    /// Starts drawing the progress of `downloads` downloads.
+    pub fn new(downloads: usize) -> Self {
+        let multi = Arc::new(MultiProgress::new());
+        let total = multi.add(
+            ProgressBar::new(0)
+                .with_style(style(TOTAL_TEMPLATE))
+                .with_prefix(format!("Total ({} mods)", downloads)),
+        );
+
+        let drawer = {
+            let multi = Arc::clone(&multi);
+            thread::spawn(move || {
+                let _result = multi.join();
+            })
+        };
+
+        Self {
+            multi,
+            total,
+            drawer: Some(drawer),
+            hidden: ProgressDrawTarget::stderr().is_hidden(),
+        }
+    }
+
+    /// Returns a `Printer` which prints above the bars while they are drawn.
+    pub fn printer(&self) -> Printer {
+        Printer {
+            bar: if self.hidden {
+                None
+            } else {
+                Some(self.total.clone())
+            },
+        }
+    }
+
+    /// Adds the bar of a single download called `title`.
+    pub fn download(&self, title: &str) -> DownloadProgress {
+        let bar = self.multi.add(
+            ProgressBar::new(0)
+                .with_style(style(DOWNLOAD_TEMPLATE))
+                .with_prefix(prefix(title)),
+        );
+        DownloadProgress {
+            bar,
+            total: self.total.clone(),
+        }
+    }
+
+    /// Finishes the bar of all downloads and waits until the last state of all bars is drawn.
+    ///
+    /// All `DownloadProgress`es have to be finished or dropped before.
+    pub fn finish(mut self) {
+        self.total.finish();
+        if let Some(drawer) = self.drawer.take() {
+            let _result = drawer.join();
+        }
+    }
+}
+
+impl Drop for Progress {
+    fn drop(&mut self) {
+        if !self.total.is_finished() {
+            self.total.abandon();
+        }
+    }
+}
+
+/// Prints messages without garbling progress bars which are drawn at the same time.
+///
+/// The default `Printer` prints to stdout.
+#[derive(Clone, Default)]
+pub struct Printer {
+    /// Bar of the group the messages are printed above.
+    bar: Option<ProgressBar>,
+}
+
+impl Printer {
+    /// Prints `message` as a line.
+    pub fn println(&self, message: impl AsRef<str>) {
+        match &self.bar {
+            Some(bar) if !bar.is_finished() => bar.println(message),
+            _ => println!("{}", message.as_ref()),
+        }
+    }
+}
+
+/// Progress bar of a single download, which also advances the bar of its group.
+#[derive(Clone)]
+pub struct DownloadProgress {
+    /// Bar of this download.
+    bar: ProgressBar,
+    /// Bar of all downloads of the group.
+    total: ProgressBar,
+}
+
+impl DownloadProgress {
+    /// Instanciates a `DownloadProgress` which doesn't draw anything.
+    pub fn hidden() -> Self {
+        Self {
+            bar: ProgressBar::hidden(),
+            total: ProgressBar::hidden(),
+        }
+    }
+
+    /// Shows `message` behind the bar, like the state of a download which didn't start yet.
+    pub fn set_message(&self, message: impl Into<String>) {
+        self.bar.set_message(message.into());
+    }
+
+    /// Sets the size of the download in bytes once it is known.
+    pub fn set_length(&self, length: u64) {
+        let old = self.bar.length();
+        self.bar.set_length(length);
+        if length >= old {
+            self.total.inc_length(length - old);
+        } else {
+            self.total
+                .set_length(self.total.length().saturating_sub(old - length));
+        }
+    }
+
+    /// Resets the bars when the download starts over.
+    pub fn restart(&self) {
+        self.total
+            .set_position(self.total.position().saturating_sub(self.bar.position()));
+        self.bar.set_position(0);
+    }
+
+    /// Advances the bars by `bytes` downloaded bytes.
+    pub fn inc(&self, bytes: u64) {
+        self.bar.inc(bytes);
+        self.total.inc(bytes);
+    }
+
+    /// Finishes the bar, showing `message` behind it.
+    pub fn finish(&self, message: impl Into<String>) {
+        self.bar.finish_with_message(message.into());
+    }
+}
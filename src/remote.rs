@@ -1,38 +1,218 @@
 //! Network functionality.
 
-use std::{collections::HashMap, thread, time::Duration};
+use std::{
+    collections::HashMap,
+    future::Future,
+    io::SeekFrom,
+    path::Path,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
-use anyhow::Result;
-use bytes::Bytes;
-use reqwest::Client;
+use anyhow::{anyhow, bail, Result};
+use futures_util::StreamExt;
+use reqwest::{
+    header::{
+        HeaderMap, HeaderValue, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, IF_RANGE, LAST_MODIFIED,
+        RANGE,
+    },
+    Client, Request, StatusCode,
+};
 use serde::Deserialize;
-use serde::Serialize;
+use tempfile::NamedTempFile;
+use tokio::{
+    fs::File,
+    io::{AsyncSeekExt, AsyncWriteExt},
+    time,
+};
+
+use crate::{
+    backend::{Download, DownloadBackend, Fetch, Status},
+    progress::DownloadProgress,
+    Config,
+};
+
+/// Maximum number of items requested from the steam workshop API at once.
+const ITEMS_PER_REQUEST: usize = 100;
+
+/// Metadata of a workshop item as returned by the steam workshop API.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ItemInfo {
+    /// Title of the requested mod.
+    pub title: String,
+    /// Last update time of the requested mod as unix timestamp.
+    pub time_updated: u64,
+    /// Steam app id of the game the mod belongs to.
+    pub consumer_app_id: u64,
+    /// Workshop items the mod requires.
+    #[serde(default)]
+    pub children: Vec<ItemChild>,
+}
+
+/// A file downloaded from a url.
+#[derive(Debug)]
+pub struct UrlFile {
+    /// Temporary file with the contents, which is deleted when dropped.
+    pub file: NamedTempFile,
+    /// `ETag` or otherwise `Last-Modified` header of the response, used to check for newer versions.
+    pub version: Option<String>,
+}
+
+/// A workshop item required by another one.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ItemChild {
+    /// Id of the required item.
+    pub publishedfileid: String,
+}
+
+impl ItemInfo {
+    /// Returns the ids of the workshop items the mod requires.
+    pub fn dependencies(&self) -> Vec<u64> {
+        self.children
+            .iter()
+            .filter_map(|child| child.publishedfileid.parse().ok())
+            .collect()
+    }
+}
+
+/// Time to wait before the first retry of a failed request, doubled for every further retry.
+const FIRST_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// Time between two status checks of a requested download.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Whether a request which failed with `error` might succeed when it is repeated.
+fn is_transient(error: &anyhow::Error) -> bool {
+    error
+        .chain()
+        .filter_map(|cause| cause.downcast_ref::<reqwest::Error>())
+        .any(|error| match error.status() {
+            Some(status) => {
+                status.is_server_error()
+                    || status == StatusCode::REQUEST_TIMEOUT
+                    || status == StatusCode::TOO_MANY_REQUESTS
+            }
+            None => !error.is_builder(),
+        })
+}
+
+/// Exponential backoff between the attempts of a request.
+struct Backoff {
+    /// Number of retries allowed.
+    retries: u32,
+    /// Number of retries so far.
+    attempt: u32,
+    /// Time to wait before the next retry.
+    delay: Duration,
+}
+
+impl Backoff {
+    /// Instanciates a new `Backoff` allowing `retries` retries.
+    const fn new(retries: u32) -> Self {
+        Self {
+            retries,
+            attempt: 0,
+            delay: FIRST_RETRY_DELAY,
+        }
+    }
+
+    /// Waits before the next attempt if `error` is transient and retries are left. Returns whether to try again.
+    ///
+    /// The retry is shown on `progress` if given, otherwise it is printed.
+    async fn retry(&mut self, error: &anyhow::Error, progress: Option<&DownloadProgress>) -> bool {
+        if self.attempt >= self.retries || !is_transient(error) {
+            return false;
+        }
+        self.attempt += 1;
+        let message = format!(
+            "{:#}, retry {}/{} in {}s",
+            error,
+            self.attempt,
+            self.retries,
+            self.delay.as_secs()
+        );
+        match progress {
+            Some(progress) => progress.set_message(message),
+            None => println!("{}", message),
+        }
+        time::sleep(self.delay).await;
+        self.delay *= 2;
+        true
+    }
+}
+
+/// A file being downloaded, which is resumed where it stopped when a request fails.
+struct PartialFile {
+    /// Writes to the temporary file.
+    writer: File,
+    /// Number of bytes downloaded so far.
+    written: u64,
+    /// Headers of the response the download started with.
+    headers: Option<HeaderMap>,
+}
+
+impl PartialFile {
+    /// Drops everything written after the downloaded bytes, like the rest of an interrupted write.
+    async fn truncate(&mut self) -> Result<()> {
+        self.writer.set_len(self.written).await?;
+        let _position = self.writer.seek(SeekFrom::Start(self.written)).await?;
+        Ok(())
+    }
+
+    /// Drops the downloaded bytes to start over.
+    async fn restart(&mut self, progress: &DownloadProgress) -> Result<()> {
+        self.written = 0;
+        self.headers = None;
+        progress.restart();
+        self.truncate().await
+    }
+}
 
 /// This struct saves the client for network operations so we don't have to recreate it for every function.
+///
+/// Clones share the same connection pool, so a clone can be moved into each concurrent download.
+#[derive(Clone)]
 pub struct Remote {
     /// The `reqwest::Client`.
-    client: Option<Client>,
+    client: Client,
+    /// Where mods are downloaded from.
+    backend: Arc<dyn DownloadBackend>,
+    /// How often a failed request of a download is retried.
+    retries: u32,
+    /// How long to wait for the backend to prepare a download.
+    max_wait: Duration,
 }
 
-impl Remote {
-    /// Instanciates a new `Remote`struct.
-    pub const fn new() -> Self {
-        Self { client: None }
+impl Default for Remote {
+    fn default() -> Self {
+        Self::new(&Config::default())
     }
+}
 
-    /// Creates a `reqwest::Client` and saves it in the struct if none exists yet.
-    fn client(&mut self) -> &Client {
-        #[allow(clippy::option_if_let_else)]
-        if let Some(ref client) = self.client {
-            client
-        } else {
-            self.client = Some(Client::new());
-            self.client.as_ref().expect("value just inserted missing")
+impl Remote {
+    /// Instanciates a new `Remote`struct downloading from the configured backend with the configured retries.
+    pub fn new(config: &Config) -> Self {
+        let client = Client::new();
+        Self {
+            backend: config.backend.build(client.clone()),
+            client,
+            retries: config.retries,
+            max_wait: Duration::from_secs(config.max_wait),
         }
     }
 
     /// Gets the mod info from the steam worshop API.
-    pub async fn get_item_info(&mut self, item_id: u64) -> Result<(String, u64)> {
+    pub async fn get_item_info(&self, item_id: u64) -> Result<ItemInfo> {
+        self.get_items_info(&[item_id])
+            .await?
+            .remove(&item_id)
+            .ok_or_else(|| anyhow!("Could not find workshop item {}!", item_id))
+    }
+
+    /// Gets the mod info of many items from the steam workshop API, using as few requests as possible.
+    ///
+    /// Items which don't exist or aren't visible are missing from the returned map.
+    pub async fn get_items_info(&self, item_ids: &[u64]) -> Result<HashMap<u64, ItemInfo>> {
         /// Struct for deserializing response from the steam workshop API.
         #[derive(Debug, Deserialize)]
         pub struct WorkshopItemInfoResponseList {
@@ -43,141 +223,484 @@ impl Remote {
         /// Struct for deserializing response from the steam workshop API.
         #[derive(Debug, Deserialize)]
         pub struct WorkshopItemInfoResponse {
-            /// Struct for deserializing response from the steam workshop API.
-            publishedfiledetails: Vec<WorkshopItemInfo>,
+            /// Details of each item, which only contain the id and result if the item wasn't found.
+            #[serde(default)]
+            publishedfiledetails: Vec<serde_json::Value>,
         }
 
         /// Struct for deserializing response from the steam workshop API.
         #[derive(Debug, Deserialize)]
-        pub struct WorkshopItemInfo {
-            /// Title of the requested mod.
-            pub title: String,
-            /// Last update time of the requested mod as unix timestamp.
-            pub time_updated: u64,
-        }
-
-        let client = self.client();
-
-        let item_info = client
-            .post("https://api.steampowered.com/ISteamRemoteStorage/GetPublishedFileDetails/v1/")
-            .form(&[
-                ("itemcount", "1"),
-                ("publishedfileids[0]", &item_id.to_string()),
-            ])
-            .send()
-            .await?
-            .json::<WorkshopItemInfoResponseList>()
-            .await?;
+        pub struct WorkshopItemResult {
+            /// Id of the item.
+            publishedfileid: String,
+            /// `1` if the item was found.
+            result: u32,
+        }
 
-        let title = &item_info
-            .response
-            .publishedfiledetails
-            .get(0)
-            .expect("workshop response empty")
-            .title;
-        let time_updated = item_info
-            .response
-            .publishedfiledetails
-            .get(0)
-            .expect("workshop response empty")
-            .time_updated;
-        Ok((title.to_string(), time_updated))
-    }
-
-    /// Downloads mods from steamworkshopdownloader.io
-    pub async fn download_item(&mut self, item_id: u64) -> Result<Bytes> {
-        /// Used to serialize the initial request.
-        #[allow(clippy::struct_excessive_bools, clippy::missing_docs_in_private_items)]
-        #[derive(Debug, Serialize)]
-        #[serde(rename_all = "camelCase")]
-        struct RequestBody {
-            published_file_id: u64,
-            collection_id: Option<u64>,
-            extract: bool,
-            hidden: bool,
-            direct: bool,
-            autodownload: bool,
-        }
-
-        /// Used to deserialize the initial response.
-        #[derive(Debug, Deserialize)]
-        struct RequestResponse {
-            /// This is used in the status and download request.
-            uuid: String,
+        let client = &self.client;
+        let mut infos = HashMap::with_capacity(item_ids.len());
+        let url = "https://api.steampowered.com/ISteamRemoteStorage/GetPublishedFileDetails/v1/";
+
+        for chunk in item_ids.chunks(ITEMS_PER_REQUEST) {
+            let mut form = vec![("itemcount".to_string(), chunk.len().to_string())];
+            form.extend(
+                chunk
+                    .iter()
+                    .enumerate()
+                    .map(|(i, item_id)| (format!("publishedfileids[{}]", i), item_id.to_string())),
+            );
+
+            let form = &form;
+            let response = self
+                .retry(None, || async move {
+                    Ok(client
+                        .post(url)
+                        .form(form)
+                        .send()
+                        .await?
+                        .error_for_status()?
+                        .json::<WorkshopItemInfoResponseList>()
+                        .await?)
+                })
+                .await?;
+
+            for details in response.response.publishedfiledetails {
+                let WorkshopItemResult {
+                    publishedfileid,
+                    result,
+                } = serde_json::from_value(details.clone())?;
+                if result == 1 {
+                    let _old =
+                        infos.insert(publishedfileid.parse()?, serde_json::from_value(details)?);
+                }
+            }
         }
+        Ok(infos)
+    }
+
+    /// Gets the mod info of `item_id` and all workshop items it requires, directly or indirectly.
+    ///
+    /// Items which aren't available are left out, including `item_id` itself.
+    pub async fn get_item_tree(&self, item_id: u64) -> Result<HashMap<u64, ItemInfo>> {
+        let mut infos: HashMap<u64, ItemInfo> = HashMap::new();
+        let mut unavailable = Vec::new();
+        let mut missing = vec![item_id];
 
-        /// Used to serialize subsequent requests.
-        #[derive(Debug, Serialize)]
-        struct Uuids {
-            /// This is used in status and download request.
-            uuids: Vec<String>,
+        while !missing.is_empty() {
+            let found = self.get_items_info(&missing).await?;
+            unavailable.extend(
+                missing
+                    .iter()
+                    .filter(|item_id| !found.contains_key(item_id)),
+            );
+            infos.extend(found);
+
+            missing = infos
+                .values()
+                .flat_map(ItemInfo::dependencies)
+                .filter(|item_id| !infos.contains_key(item_id) && !unavailable.contains(item_id))
+                .collect();
+            missing.sort_unstable();
+            missing.dedup();
         }
+        Ok(infos)
+    }
 
-        /// Used to deserialize status responses.
+    /// Gets the ids of the workshop items in a collection, in the order of the collection.
+    pub async fn get_collection_details(&self, collection_id: u64) -> Result<Vec<u64>> {
+        /// Struct for deserializing response from the steam workshop API.
         #[derive(Debug, Deserialize)]
-        struct StatusResponse {
-            /// The status of the requested file.
-            status: String,
+        pub struct CollectionDetailsResponseList {
+            /// Struct for deserializing response from the steam workshop API.
+            response: CollectionDetailsResponse,
         }
 
-        let client = self.client();
-        println!("Requesting download via steamworkshopdownloader.io");
+        /// Struct for deserializing response from the steam workshop API.
+        #[derive(Debug, Deserialize)]
+        pub struct CollectionDetailsResponse {
+            /// Struct for deserializing response from the steam workshop API.
+            #[serde(default)]
+            collectiondetails: Vec<CollectionDetails>,
+        }
 
-        let request_body = RequestBody {
-            published_file_id: item_id,
-            collection_id: None,
-            extract: true,
-            hidden: false,
-            direct: false,
-            autodownload: false,
-        };
+        /// Struct for deserializing response from the steam workshop API.
+        #[derive(Debug, Deserialize)]
+        pub struct CollectionDetails {
+            /// Items in the collection, missing if the id is not a collection.
+            #[serde(default)]
+            children: Option<Vec<ItemChild>>,
+        }
 
-        let download_request_response = client
-            .post("https://backend-01-prd.steamworkshopdownloader.io/api/download/request")
-            .body(serde_json::to_string(&request_body)?)
-            .send()
-            .await?
-            .json::<RequestResponse>()
+        let client = &self.client;
+        let collection_id_text = collection_id.to_string();
+        let form = &[
+            ("collectioncount", "1"),
+            ("publishedfileids[0]", collection_id_text.as_str()),
+        ];
+
+        let details = self
+            .retry(None, || async move {
+                Ok(client
+                    .post(
+                        "https://api.steampowered.com/ISteamRemoteStorage/GetCollectionDetails/v1/",
+                    )
+                    .form(form)
+                    .send()
+                    .await?
+                    .error_for_status()?
+                    .json::<CollectionDetailsResponseList>()
+                    .await?)
+            })
             .await?;
 
-        let download_link = format!(
-            "https://backend-01-prd.steamworkshopdownloader.io/api/download/transmit?uuid={}",
-            download_request_response.uuid
-        );
+        let children = details
+            .response
+            .collectiondetails
+            .into_iter()
+            .next()
+            .and_then(|details| details.children)
+            .ok_or_else(|| anyhow!("{} is not a workshop collection!", collection_id))?;
 
-        println!("Waiting for file to be ready.");
+        Ok(children
+            .iter()
+            .filter_map(|child| child.publishedfileid.parse().ok())
+            .collect())
+    }
 
-        let status_request_body = Uuids {
-            uuids: vec![download_request_response.uuid.clone()],
+    /// Runs `operation` until it succeeds, retrying transient errors with exponential backoff.
+    ///
+    /// Retries are shown on `progress` if given, otherwise they are printed.
+    async fn retry<T, F, Fut>(
+        &self,
+        progress: Option<&DownloadProgress>,
+        mut operation: F,
+    ) -> Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let mut backoff = Backoff::new(self.retries);
+        loop {
+            let error = match operation().await {
+                Ok(value) => return Ok(value),
+                Err(error) => error,
+            };
+            if !backoff.retry(&error, progress).await {
+                return Err(error);
+            }
+        }
+    }
+
+    /// Downloads the body of `request` into a temporary file in `directory`, reporting the downloaded bytes to `progress`.
+    ///
+    /// Failed transfers are retried and resumed with range requests where the server supports them.
+    /// Returns `None` if the server answered that the file was not modified,
+    /// otherwise the file and the headers of the response the download started with.
+    async fn download_file(
+        &self,
+        request: Request,
+        progress: &DownloadProgress,
+        directory: &Path,
+    ) -> Result<Option<(NamedTempFile, HeaderMap)>> {
+        let file = NamedTempFile::new_in(directory)?;
+        let mut partial = PartialFile {
+            writer: File::from_std(file.reopen()?),
+            written: 0,
+            headers: None,
         };
 
+        let mut backoff = Backoff::new(self.retries);
         loop {
-            let status_response = client
-                .post("https://backend-01-prd.steamworkshopdownloader.io/api/download/status")
-                .body(serde_json::to_string(&status_request_body)?)
-                .send()
-                .await?
-                .json::<HashMap<String, StatusResponse>>()
-                .await?;
+            let attempt = request
+                .try_clone()
+                .ok_or_else(|| anyhow!("The download request can't be repeated!"))?;
+            match self.transfer(attempt, &mut partial, progress).await {
+                Ok(true) => break,
+                Ok(false) => return Ok(None),
+                Err(error) => {
+                    if !backoff.retry(&error, Some(progress)).await {
+                        return Err(error);
+                    }
+                }
+            }
+        }
+
+        Ok(Some((file, partial.headers.unwrap_or_default())))
+    }
+
+    /// Sends `request`, continuing `partial` if it already has downloaded bytes, and writes the body to it.
+    ///
+    /// Returns `false` if the server answered that the file was not modified.
+    async fn transfer(
+        &self,
+        mut request: Request,
+        partial: &mut PartialFile,
+        progress: &DownloadProgress,
+    ) -> Result<bool> {
+        partial.truncate().await?;
+        if partial.written > 0 {
+            let validator = partial
+                .headers
+                .as_ref()
+                .and_then(|headers| headers.get(ETAG).or_else(|| headers.get(LAST_MODIFIED)))
+                .cloned();
+            let headers = request.headers_mut();
+            let _old = headers.insert(
+                RANGE,
+                HeaderValue::from_str(&format!("bytes={}-", partial.written))?,
+            );
+            if let Some(validator) = validator {
+                let _old = headers.insert(IF_RANGE, validator);
+            }
+        }
 
-            if status_response
-                .get(
-                    status_request_body
-                        .uuids
-                        .get(0)
-                        .expect("no uuid in response"),
-                )
-                .expect("Uuid incorrect")
-                .status
-                == "prepared"
+        let response = self.client.execute(request).await?;
+        if response.status() == StatusCode::NOT_MODIFIED {
+            return Ok(false);
+        }
+        let response = response.error_for_status()?;
+        if partial.written > 0 && response.status() != StatusCode::PARTIAL_CONTENT {
+            partial.restart(progress).await?;
+        }
+        if partial.headers.is_none() {
+            partial.headers = Some(response.headers().clone());
+        }
+        if let Some(length) = response.content_length() {
+            progress.set_length(partial.written + length);
+        }
+        progress.set_message("");
+
+        let mut body = response.bytes_stream();
+        while let Some(chunk) = body.next().await {
+            let chunk = chunk?;
+            partial.writer.write_all(&chunk).await?;
+            partial.written += chunk.len() as u64;
+            progress.inc(chunk.len() as u64);
+        }
+        partial.writer.flush().await?;
+        Ok(true)
+    }
+
+    /// Downloads mods of the game with the steam app id `app_id` from the configured backend.
+    ///
+    /// Archives are written to `directory`. Failed requests are retried, the state of the download is shown on `progress`.
+    pub async fn download_item(
+        &self,
+        item_id: u64,
+        app_id: u64,
+        progress: &DownloadProgress,
+        directory: &Path,
+    ) -> Result<Download> {
+        let backend = self.backend.name();
+        progress.set_message(format!("requesting via {}", backend));
+        let ticket = self
+            .retry(Some(progress), || {
+                self.backend.request(item_id, app_id, progress)
+            })
+            .await?;
+
+        let started = Instant::now();
+        loop {
+            match self
+                .retry(Some(progress), || self.backend.status(&ticket))
+                .await?
             {
-                break;
+                Status::Ready => break,
+                Status::Failed(reason) => {
+                    bail!("{} could not prepare {}: {}", backend, item_id, reason)
+                }
+                Status::Pending(state) => progress.set_message(format!("waiting, {}", state)),
+            }
+            if started.elapsed() >= self.max_wait {
+                bail!(
+                    "{} did not prepare {} within {} seconds!",
+                    backend,
+                    item_id,
+                    self.max_wait.as_secs()
+                );
             }
-            println!("...");
-            thread::sleep(Duration::from_secs(1));
+            time::sleep(POLL_INTERVAL).await;
         }
 
-        println!("File ready, downloading now!");
-        Ok(client.get(download_link).send().await?.bytes().await?)
+        progress.set_message("downloading");
+        match self
+            .retry(Some(progress), || self.backend.fetch(&ticket))
+            .await?
+        {
+            Fetch::Request(request) => {
+                let (file, _headers) = self
+                    .download_file(*request, progress, directory)
+                    .await?
+                    .ok_or_else(|| anyhow!("{} sent no file for {}!", backend, item_id))?;
+                Ok(Download::Archive(file))
+            }
+            Fetch::Done(download) => Ok(download),
+        }
+    }
+
+    /// Downloads the file at `url` into `directory`.
+    ///
+    /// `version` is the version of an earlier download, if the file didn't change since then `None` is returned.
+    /// Failed requests are retried, the download is shown on `progress`.
+    pub async fn download_url(
+        &self,
+        url: &str,
+        version: Option<&str>,
+        progress: &DownloadProgress,
+        directory: &Path,
+    ) -> Result<Option<UrlFile>> {
+        let mut request = self.client.get(url);
+        if let Some(version) = version {
+            request = if version.starts_with('"') || version.starts_with("W/") {
+                request.header(IF_NONE_MATCH, version)
+            } else {
+                request.header(IF_MODIFIED_SINCE, version)
+            };
+        }
+
+        let (file, headers) = match self
+            .download_file(request.build()?, progress, directory)
+            .await?
+        {
+            Some(download) => download,
+            None => return Ok(None),
+        };
+        let new_version = headers
+            .get(ETAG)
+            .or_else(|| headers.get(LAST_MODIFIED))
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+        if new_version.is_some() && new_version.as_deref() == version {
+            return Ok(None);
+        }
+
+        Ok(Some(UrlFile {
+            file,
+            version: new_version,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::VecDeque,
+        fs,
+        io::{BufRead, BufReader, Write},
+        net::TcpListener,
+        sync::Mutex,
+        thread,
+    };
+
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::progress::Progress;
+
+    /// Body of the served file.
+    const BODY: &[u8] = b"0123456789";
+
+    /// Starts an HTTP server on localhost which sends one of `responses` per request, as raw bytes, and closes the
+    /// connection. Returns its address and the `Range` header of every request.
+    fn serve(responses: Vec<Vec<u8>>) -> (String, Arc<Mutex<Vec<Option<String>>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = format!("http://{}/mod.zip", listener.local_addr().unwrap());
+        let ranges = Arc::new(Mutex::new(Vec::new()));
+        let requests = Arc::clone(&ranges);
+        let mut responses = VecDeque::from(responses);
+        let _server = thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut range = None;
+                for line in BufReader::new(stream.try_clone().unwrap()).lines() {
+                    let line = line.unwrap();
+                    if line.is_empty() {
+                        break;
+                    }
+                    if let Some((name, value)) = line.split_once(':') {
+                        if name.eq_ignore_ascii_case("range") {
+                            range = Some(value.trim().to_string());
+                        }
+                    }
+                }
+                requests.lock().unwrap().push(range);
+                let response = responses.pop_front().expect("unexpected request");
+                stream.write_all(&response).unwrap();
+            }
+        });
+        (address, ranges)
+    }
+
+    /// Returns a response with `status` and `body`, announcing `length` bytes.
+    fn response(status: &str, length: usize, body: &[u8]) -> Vec<u8> {
+        let mut response = format!(
+            "HTTP/1.1 {}\r\nContent-Length: {}\r\nETag: \"v1\"\r\nConnection: close\r\n\r\n",
+            status, length
+        )
+        .into_bytes();
+        response.extend_from_slice(body);
+        response
+    }
+
+    /// Downloads `address` with one retry allowed and returns the downloaded bytes.
+    async fn download(address: &str) -> Result<Vec<u8>> {
+        let dir = TempDir::new().unwrap();
+        let remote = Remote::new(&Config {
+            retries: 1,
+            ..Config::default()
+        });
+        let progress = Progress::new(1);
+        let bar = progress.download("Mod");
+        let file = remote.download_url(address, None, &bar, dir.path()).await;
+        bar.finish("done");
+        progress.finish();
+        Ok(fs::read(file?.expect("no file was sent").file.path()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn transient_errors_are_retried() {
+        let (address, ranges) = serve(vec![
+            response("503 Service Unavailable", 0, b""),
+            response("200 OK", BODY.len(), BODY),
+        ]);
+
+        assert_eq!(download(&address).await.unwrap(), BODY);
+        assert_eq!(*ranges.lock().unwrap(), vec![None, None]);
+    }
+
+    #[tokio::test]
+    async fn permanent_errors_are_not_retried() {
+        let (address, ranges) = serve(vec![response("404 Not Found", 0, b"")]);
+
+        assert!(download(&address).await.is_err());
+        assert_eq!(ranges.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn interrupted_downloads_are_resumed() {
+        let (address, ranges) = serve(vec![
+            response("200 OK", BODY.len(), &BODY[..4]),
+            response("206 Partial Content", BODY.len() - 4, &BODY[4..]),
+        ]);
+
+        assert_eq!(download(&address).await.unwrap(), BODY);
+        assert_eq!(
+            *ranges.lock().unwrap(),
+            vec![None, Some(String::from("bytes=4-"))]
+        );
+    }
+
+    #[tokio::test]
+    async fn downloads_start_over_if_the_server_ignores_the_range() {
+        let (address, ranges) = serve(vec![
+            response("200 OK", BODY.len(), &BODY[..4]),
+            response("200 OK", BODY.len(), BODY),
+        ]);
+
+        assert_eq!(download(&address).await.unwrap(), BODY);
+        assert_eq!(
+            *ranges.lock().unwrap(),
+            vec![None, Some(String::from("bytes=4-"))]
+        );
     }
 }
@@ -0,0 +1,94 @@
+//! Where installed mods come from.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// Marks ids which were derived from a source instead of assigned by the steam workshop.
+///
+/// Toml integers are signed, so derived ids stay below `i64::MAX`.
+const DERIVED_ID_BIT: u64 = 1 << 62;
+
+/// Origin of an installed mod, used to find newer versions of it.
+#[derive(Debug, Default, Deserialize, Serialize, Clone, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Source {
+    /// Steam workshop item with the id of the mod.
+    #[default]
+    Workshop,
+    /// `.zip` archive or folder on this computer.
+    Local {
+        /// Absolute path to the archive or folder.
+        path: String,
+    },
+    /// Git repository, cloned next to the installed mods.
+    Git {
+        /// Address of the repository.
+        url: String,
+        /// Branch or tag to follow, the default branch if not set.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        reference: Option<String>,
+        /// Folder inside the repository which contains the mod.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        subdir: Option<String>,
+    },
+    /// Archive downloaded from a http or https address.
+    Url {
+        /// Address of the archive.
+        url: String,
+        /// Expected SHA-256 hash of the archive. Downloads with another hash are rejected.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        sha256: Option<String>,
+    },
+}
+
+impl Source {
+    /// Whether the mod is a steam workshop item.
+    pub const fn is_workshop(&self) -> bool {
+        matches!(self, Self::Workshop)
+    }
+}
+
+/// Returns a stable id for a mod which isn't from the steam workshop, derived from its location.
+///
+/// A bit far above any workshop id is set so the id never collides with one.
+pub fn derived_id(location: &str) -> u64 {
+    (fnv1a(location.as_bytes()) >> 2) | DERIVED_ID_BIT
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Workshop => write!(f, "workshop"),
+            Self::Local { path } => write!(f, "local {}", path),
+            Self::Git {
+                url,
+                reference,
+                subdir,
+            } => {
+                write!(f, "git {}", url)?;
+                if let Some(reference) = reference {
+                    write!(f, " {}", reference)?;
+                }
+                if let Some(subdir) = subdir {
+                    write!(f, " /{}", subdir)?;
+                }
+                Ok(())
+            }
+            Self::Url { url, sha256 } => {
+                write!(f, "url {}", url)?;
+                if let Some(sha256) = sha256 {
+                    write!(f, " sha256 {}", sha256)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// 64 bit FNV-1a hash, which unlike the std hashers is stable across versions.
+pub(crate) fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}